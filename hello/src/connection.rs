@@ -0,0 +1,304 @@
+//! 연결 하나에서 여러 요청 처리 (HTTP/1.1 keep-alive, 파이프라이닝)
+//!
+//! HTTP/1.1은 `Connection: close`가 없으면 연결을 유지하고, HTTP/1.0은 `Connection: keep-alive`가 있을 때만 유지함
+//! 응답을 기다리지 않고 이어서 보낸 요청(파이프라이닝)은 받은 순서대로 처리하므로 응답 순서도 같음
+use crate::{Limits, Request, RequestError, Response, Router, Version};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+// 다음 요청을 기다리는 동안 서버 종료 요청을 확인하는 간격
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+// 연결을 닫기 전에 클라이언트가 보낸 나머지 데이터를 버리며 기다리는 최대 시간
+const LINGER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 연결이 닫히거나, 제한에 걸리거나, 클라이언트가 종료를 요청할 때까지 요청을 읽어 응답
+/// `stop`이 설정되면 이미 도착한 요청까지만 응답하고 연결을 닫음
+/// 요청을 하나 이상 처리한 뒤 다음 요청을 기다리는 동안 `waiting`(작업 스레드를 기다리는 연결 수)이
+/// 0보다 크면 `idle_timeout`까지 기다리지 않고 연결을 닫음
+pub(crate) fn serve_connection(
+    stream: TcpStream,
+    router: &Router,
+    limits: &Limits,
+    stop: &AtomicBool,
+    waiting: &AtomicUsize,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut writer = BufWriter::new(&stream);
+    let mut served = 0;
+
+    loop {
+        // 첫 요청은 연결 직후 아직 도착하지 않았을 수 있으므로 다른 연결에 양보하지 않음
+        let waiting = (served > 0).then_some(waiting);
+        if !wait_for_request(&reader, limits.idle_timeout, stop, waiting)? {
+            // 다른 연결에 양보할 때는 보내지 않은 응답도, 도착한 요청도 없으므로
+            // `linger`로 작업 스레드를 더 붙잡지 않고 바로 닫음
+            if waiting.is_some_and(|waiting| waiting.load(Ordering::SeqCst) > 0) {
+                let _ = stream.shutdown(Shutdown::Both);
+                return Ok(());
+            }
+            break;
+        }
+        let mut request = match Request::read(&mut reader, limits) {
+            Ok(Some(request)) => request,
+            // 클라이언트가 연결을 닫았거나 제한 시간 동안 다음 요청이 없음
+            Ok(None) => break,
+            Err(RequestError::Io(err)) if is_timeout(&err) => break,
+            Err(RequestError::Io(err)) => return Err(err),
+            // 잘못된 요청 뒤의 데이터는 어디서 다음 요청이 시작하는지 알 수 없으므로 응답 후 종료
+            Err(err) => {
+                let (status, _) = err.status().unwrap_or((400, "Bad Request"));
+                let mut response = Response::text(status, format!("{err}\n"));
+                response.set_header("Connection", "close");
+                response.write_to(&mut writer)?;
+                break;
+            }
+        };
+        served += 1;
+
+        let mut response = router.handle(&mut request);
+        // 핸들러가 직접 연결 종료를 요청하거나, 처리하는 동안 서버 종료가 요청되었을 수도 있음
+        let keep_alive = wants_keep_alive(&request)
+            && served < limits.max_requests
+            && !stop.load(Ordering::SeqCst)
+            && !response
+                .header("Connection")
+                .is_some_and(|value| has_token(value, "close"));
+        if !keep_alive {
+            response.set_header("Connection", "close");
+        } else if request.version == Version::Http10 {
+            response.set_header("Connection", "keep-alive");
+        }
+        if request.method == "HEAD" {
+            response.write_head_to(&mut writer)?;
+        } else {
+            response.write_to(&mut writer)?;
+        }
+
+        if !keep_alive {
+            break;
+        }
+        // 이미 도착한 다음 요청이 없을 때만 보냄 (파이프라이닝된 요청의 응답은 모아서 한 번에)
+        if reader.buffer().is_empty() {
+            writer.flush()?;
+        }
+    }
+
+    writer.flush()?;
+    drop(writer);
+    linger(&stream);
+    Ok(())
+}
+
+// 다음 요청의 첫 바이트가 도착할 때까지 기다림
+// `idle_timeout` 동안 오지 않거나, 서버가 종료 중이거나, 작업 스레드를 기다리는 연결이 있으면
+// `false` (이미 도착한 요청은 처리)
+fn wait_for_request(
+    reader: &BufReader<&TcpStream>,
+    idle_timeout: Duration,
+    stop: &AtomicBool,
+    waiting: Option<&AtomicUsize>,
+) -> io::Result<bool> {
+    if !reader.buffer().is_empty() {
+        return Ok(true);
+    }
+
+    let stream = reader.get_ref();
+    let deadline = Instant::now() + idle_timeout;
+    let mut byte = [0; 1];
+    loop {
+        let stopping = stop.load(Ordering::SeqCst)
+            || waiting.is_some_and(|waiting| waiting.load(Ordering::SeqCst) > 0);
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Ok(false);
+        }
+        // 종료 중이거나 양보할 때는 이미 도착한 데이터가 있는지만 확인
+        let wait = if stopping {
+            Duration::from_millis(1)
+        } else {
+            left.min(STOP_CHECK_INTERVAL)
+        };
+        stream.set_read_timeout(Some(wait))?;
+        match stream.peek(&mut byte) {
+            // 연결이 닫힌 경우(0바이트)는 `Request::read`가 `None`으로 처리
+            Ok(_) => break,
+            Err(err) if is_timeout(&err) && !stopping => {}
+            Err(err) if is_timeout(&err) => return Ok(false),
+            Err(err) => return Err(err),
+        }
+    }
+
+    // 요청을 읽는 중에도 이 시간 동안 아무것도 오지 않으면 연결 종료
+    stream.set_read_timeout(Some(idle_timeout))?;
+    Ok(true)
+}
+
+// HTTP/1.1은 기본으로 유지, HTTP/1.0은 요청한 경우만 유지
+fn wants_keep_alive(request: &Request) -> bool {
+    let connection = request.header("Connection").unwrap_or_default();
+    match request.version {
+        Version::Http11 => !has_token(connection, "close"),
+        Version::Http10 => has_token(connection, "keep-alive"),
+    }
+}
+
+// `Connection: keep-alive, Upgrade`처럼 쉼표로 구분된 목록에 `token`이 있는지 여부
+fn has_token(value: &str, token: &str) -> bool {
+    value
+        .split(',')
+        .any(|item| item.trim().eq_ignore_ascii_case(token))
+}
+
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+// 읽지 않은 데이터가 남은 채로 소켓을 닫으면 TCP RST가 전송되어,
+// 클라이언트가 마지막 응답을 읽기 전에 연결 에러를 받을 수 있음
+// 쓰기 쪽을 먼저 닫고, 클라이언트가 닫을 때까지 남은 데이터를 읽어서 버림
+fn linger(mut stream: &TcpStream) {
+    if stream.shutdown(Shutdown::Write).is_err() {
+        return;
+    }
+    let deadline = Instant::now() + LINGER_TIMEOUT;
+    let mut buf = [0; 4096];
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        if left.is_zero() || stream.set_read_timeout(Some(left)).is_err() {
+            break;
+        }
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    // 연결 하나를 처리하는 서버에 `input`을 한 번에 보내고, 서버가 닫을 때까지 받은 응답
+    fn exchange(limits: Limits, input: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut router = Router::new();
+            router.get("/:name", |request| {
+                Response::text(200, request.param("name").unwrap_or_default().to_string())
+            });
+            let (stream, _) = listener.accept().unwrap();
+            serve_connection(
+                stream,
+                &router,
+                &limits,
+                &AtomicBool::new(false),
+                &AtomicUsize::new(0),
+            )
+            .unwrap();
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+        client.write_all(input.as_bytes()).unwrap();
+        let mut output = String::new();
+        client.read_to_string(&mut output).unwrap();
+        drop(client);
+        server.join().unwrap();
+        output
+    }
+
+    // 응답마다 (본문, `Connection` 헤더)
+    fn responses(output: &str) -> Vec<(String, Option<String>)> {
+        output
+            .split("HTTP/1.1 ")
+            .skip(1)
+            .map(|response| {
+                let (head, body) = response.split_once("\r\n\r\n").unwrap();
+                let connection = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Connection: "))
+                    .map(String::from);
+                (body.to_string(), connection)
+            })
+            .collect()
+    }
+
+    fn bodies(output: &str) -> Vec<String> {
+        responses(output)
+            .into_iter()
+            .map(|(body, _)| body)
+            .collect()
+    }
+
+    #[test]
+    fn pipelined_requests_in_order() {
+        let output = exchange(
+            Limits::default(),
+            "GET /one HTTP/1.1\r\n\r\n\
+             GET /two HTTP/1.1\r\n\r\n\
+             GET /three HTTP/1.1\r\nConnection: close\r\n\r\n\
+             GET /ignored HTTP/1.1\r\n\r\n",
+        );
+        assert_eq!(
+            vec![
+                ("one".to_string(), None),
+                ("two".to_string(), None),
+                ("three".to_string(), Some("close".to_string()))
+            ],
+            responses(&output)
+        );
+    }
+
+    #[test]
+    fn max_requests_per_connection() {
+        let limits = Limits {
+            max_requests: 2,
+            ..Limits::default()
+        };
+        let output = exchange(limits, &"GET /again HTTP/1.1\r\n\r\n".repeat(3));
+        assert_eq!(
+            vec![
+                ("again".to_string(), None),
+                ("again".to_string(), Some("close".to_string()))
+            ],
+            responses(&output)
+        );
+    }
+
+    #[test]
+    fn http10_and_bad_requests_close() {
+        let output = exchange(
+            Limits::default(),
+            "GET /old HTTP/1.0\r\n\r\nGET /more HTTP/1.0\r\n\r\n",
+        );
+        assert_eq!(vec!["old"], bodies(&output));
+
+        let output = exchange(
+            Limits::default(),
+            "GET /old HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /bad\r\n\r\nGET /more HTTP/1.1\r\n\r\n",
+        );
+        assert_eq!(Some("keep-alive".to_string()), responses(&output)[0].1);
+        assert!(output.contains("HTTP/1.1 400 Bad Request"));
+        assert!(!output.contains("more"));
+    }
+
+    #[test]
+    fn idle_connections_time_out() {
+        let limits = Limits {
+            idle_timeout: Duration::from_millis(50),
+            ..Limits::default()
+        };
+        // 응답 후 다음 요청을 보내지 않으면 서버가 연결을 닫아 `read_to_string`이 끝남
+        let started = Instant::now();
+        let output = exchange(limits, "GET /idle HTTP/1.1\r\n\r\n");
+        assert_eq!(vec!["idle"], bodies(&output));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -1,3 +1,17 @@
+mod connection;
+mod pool;
+mod request;
+mod response;
+mod router;
+mod server;
+mod static_files;
+
+pub use request::{Limits, Request, RequestError, Version};
+pub use response::Response;
+pub use router::{Handler, Router};
+pub use server::{default_workers, Server, ServerHandle, WORKERS_PER_CPU};
+pub use static_files::StaticFiles;
+
 use std::{
     sync::{mpsc, Arc, Mutex},
     thread,
@@ -1,59 +1,62 @@
 //! 웹 서버 만들기
 //!
-use hello::ThreadPool;
-use std::{
-    fs,
-    io::{prelude::*, BufReader},
-    net::{TcpListener, TcpStream},
-    thread,
-    time::Duration,
-};
-
-fn main() {
-    // 1. TCP 연결 수신 대기
-    // - `bind`의 `Result<T, E>` 반환 중 에러 발생 시 `unwrap`에 의해 프로그램 종료
-    let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
-    // 스레드 풀 생성 (사이즈: 4)
-    let pool = ThreadPool::new(4);
-
-    // - `incoming`은 `TcpStream` 타입의 스트림 시퀀스를 제공하는 반복자 반환
-    // - 하나의 스트림은 클라이언트와 서버간 개방형 연결(세션)을 나타냄
-    // - 종료 테스트를 위해 2개의 세션만 수신하고 종료하도록 변경
-    for stream in listener.incoming().take(4) {
-        // - 스트림에 에러가 있을 경우 `unwrap`에 의해 프로그램 종료 (ex. 동시 연결 수 제한)
-        let stream = stream.unwrap();
+use hello::{Response, Router, Server, StaticFiles};
+use std::{env, fs, process, thread, time::Duration};
+
+// 경로별 핸들러 등록
+// `/static/` 아래는 `static_root` 디렉터리의 파일로 응답
+fn routes(static_root: String) -> Router {
+    let files = StaticFiles::new(static_root);
+    let mut router = Router::new();
+    router
+        .get("/", |_| page(200, "hello.html"))
+        .get("/static/*path", move |request| {
+            files.serve(request, request.param("path").unwrap_or_default())
+        })
+        .get("/sleep", |_| {
+            // /sleep URI 접속 시 5초 대기 후 느린 반환
+            thread::sleep(Duration::from_secs(5));
+            page(200, "hello.html")
+        })
+        .not_found(|_| page(404, "404.html"));
+    router
+}
 
-        // 요청별로 스레드 처리
-        pool.execute(|| {
-            handle_connection(stream);
-        });
+// HTML 파일을 읽어 응답
+fn page(status: u16, filename: &str) -> Response {
+    match fs::read(filename) {
+        Ok(contents) => Response::html(status, contents),
+        Err(err) => Response::text(500, format!("{filename}: {err}\n")),
     }
 }
 
-fn handle_connection(mut stream: TcpStream) {
-    let buf_reader = BufReader::new(&mut stream);
-    // HTTP 첫 번째 요청 라인만 확인
-    // 첫 번째 `unwrap`은 `Option`을 처리해 `next` 반복자의 아이템을 가져옴
-    // 두 번째 `unwrap`은 `Result`를 처리해 기존 `map`의 `unwrap`과 동일한 효과
-    let request_line = buf_reader.lines().next().unwrap().unwrap();
-
-    // 패턴 매치를 위해 `request_line` 슬라이스 사용
-    let (status_line, filename) = match &request_line[..] {
-        "GET / HTTP/1.1" => ("HTTP/1.1 200 OK", "hello.html"),
-        "GET /sleep HTTP/1.1" => {
-            // /sleep URI 접속 시 5초 대기 후 느린 반환
-            thread::sleep(Duration::from_secs(5));
-            ("HTTP/1.1 200 OK", "hello.html")
-        }
-        _ => ("HTTP/1.1 404 NOT FOUND", "404.html"),
-    };
+fn main() {
+    // 정적 파일 디렉터리는 첫 번째 인수로 지정 (기본값: `static`)
+    let static_root = env::args().nth(1).unwrap_or_else(|| "static".to_string());
 
-    let contents = fs::read_to_string(filename).unwrap();
-    let length = contents.len();
+    // 1. TCP 연결 수신 대기
+    // - `bind`의 `Result<T, E>` 반환 중 에러 발생 시 `unwrap`에 의해 프로그램 종료
+    // - 연결마다 스레드 풀의 스레드 하나가 연결이 유지되는 동안 이어서 처리
+    let mut server = Server::bind("127.0.0.1:7878", routes(static_root)).unwrap();
+    // 작업 스레드 수는 `WORKERS` 환경변수로 지정 (실행 명령어: WORKERS=16 cargo run)
+    if let Ok(workers) = env::var("WORKERS") {
+        server.workers = match workers.parse() {
+            Ok(workers) if workers > 0 => workers,
+            _ => {
+                eprintln!("WORKERS must be a positive number: {workers}");
+                process::exit(2);
+            }
+        };
+    }
 
-    let response = format!("{status_line}\r\nContent-Length: {length}\r\n\r\n{contents}");
+    // 2. Ctrl-C(SIGINT)나 SIGTERM을 받으면 새 연결을 받지 않고,
+    //    처리 중인 요청이 끝나길 기다린 뒤 (최대 `drain_timeout`) 작업 스레드를 종료
+    let handle = server.handle();
+    ctrlc::set_handler(move || {
+        println!("Shutting down.");
+        handle.shutdown();
+    })
+    .unwrap();
 
-    // `as_bytes()`: 문자열을 바이트로 변환
-    // `write_all()`: `&u[8]`을 받아 연결(`stream`)쪽으로 직접 보냄
-    stream.write_all(response.as_bytes()).unwrap();
+    server.run().unwrap();
 }
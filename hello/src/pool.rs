@@ -0,0 +1,91 @@
+//! 서버가 연결을 처리하는 작업 스레드 묶음
+//!
+//! 튜토리얼의 `ThreadPool`과 달리
+//! - 작업마다 표준 출력에 기록하지 않음
+//! - 작업이 패닉해도 그 스레드가 다음 작업을 계속 받음 (작업 스레드 수가 줄지 않음)
+//! - 종료 시 `join`이 실패해도 다시 패닉하지 않음
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+pub(crate) struct Workers {
+    threads: Vec<thread::JoinHandle<()>>,
+    sender: Option<mpsc::Sender<Job>>, // `drop`에서 먼저 닫아 작업 스레드를 끝냄
+}
+
+impl Workers {
+    pub(crate) fn new(size: usize) -> Workers {
+        assert!(size > 0, "worker count must be positive");
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let threads = (0..size)
+            .map(|id| {
+                let receiver = Arc::clone(&receiver);
+                thread::Builder::new()
+                    .name(format!("worker-{id}"))
+                    .spawn(move || loop {
+                        // 작업은 잠금을 놓은 뒤 실행하므로 잠금이 독성 상태가 되지는 않지만, 되더라도 계속 사용
+                        let job = receiver
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .recv();
+                        let Ok(job) = job else {
+                            break;
+                        };
+                        // 패닉 메시지는 기본 패닉 훅이 표준 에러에 출력
+                        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                    })
+                    .expect("failed to spawn worker thread")
+            })
+            .collect();
+
+        Workers {
+            threads,
+            sender: Some(sender),
+        }
+    }
+
+    /// 작업을 대기열에 넣음 (빈 작업 스레드가 순서대로 가져감)
+    pub(crate) fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        // 작업 스레드는 `drop` 전에는 끝나지 않으므로 보내기는 실패하지 않음
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(Box::new(job));
+        }
+    }
+}
+
+impl Drop for Workers {
+    // 대기열에 남은 작업까지 처리한 뒤 모든 작업 스레드를 `join`
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn survives_panicking_jobs() {
+        let done = Arc::new(AtomicUsize::new(0));
+        let workers = Workers::new(2);
+        for i in 0..10 {
+            let done = Arc::clone(&done);
+            workers.execute(move || {
+                if i % 2 == 0 {
+                    panic!("job {i} failed");
+                }
+                done.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        drop(workers);
+        assert_eq!(5, done.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,543 @@
+//! HTTP/1.1 요청 파싱
+//!
+//! 요청 라인, 헤더, 본문(`Content-Length` 또는 `chunked`)을 읽어 `Request`로 만듦
+//! 형식이 잘못되었거나 제한을 넘는 요청은 응답할 상태 코드를 가진 에러가 됨
+use std::fmt;
+use std::io::{self, BufRead};
+use std::time::Duration;
+
+/// 요청 크기와 연결 하나의 제한
+#[derive(Clone, Debug)]
+pub struct Limits {
+    pub max_header_bytes: usize, // 요청 라인과 헤더 전체의 크기 (넘으면 431)
+    pub max_headers: usize,      // 헤더 개수 (넘으면 431)
+    pub max_body_bytes: usize,   // 본문 크기 (넘으면 413)
+    pub idle_timeout: Duration,  // 다음 요청을 기다리는 시간 (지나면 연결 종료, 0이면 안 됨)
+    pub max_requests: usize,     // 연결 하나에서 처리할 요청 수 (마지막 응답에 `Connection: close`)
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_header_bytes: 8 * 1024,
+            max_headers: 100,
+            max_body_bytes: 1024 * 1024,
+            idle_timeout: Duration::from_secs(5),
+            max_requests: 100,
+        }
+    }
+}
+
+/// HTTP 버전
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Version {
+    Http10,
+    Http11,
+}
+
+/// 파싱한 요청
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,                 // 퍼센트 인코딩을 푼 경로 (`?` 앞부분)
+    pub raw_path: String,             // 받은 그대로의 경로 (`%2F`가 `/`와 구분되도록 라우팅에 사용)
+    pub query: Vec<(String, String)>, // 쿼리 문자열의 (이름, 값) 목록 (받은 순서대로)
+    pub version: Version,
+    pub headers: Vec<(String, String)>, // (이름, 값) 목록 (이름은 받은 그대로)
+    pub body: Vec<u8>,
+    pub params: Vec<(String, String)>, // 라우터가 경로 패턴에서 추출한 (이름, 값) 목록
+}
+
+impl Request {
+    /// 요청 하나를 읽음
+    /// 요청을 시작하기 전에 연결이 닫히면 `None`
+    pub fn read(
+        reader: &mut impl BufRead,
+        limits: &Limits,
+    ) -> Result<Option<Request>, RequestError> {
+        // 요청 라인과 헤더가 함께 쓰는 크기 제한
+        let mut budget = limits.max_header_bytes;
+
+        // 요청 앞의 빈 줄은 무시 (RFC 9112 2.2)
+        let line = loop {
+            match read_line(reader, &mut budget)? {
+                None => return Ok(None),
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break line,
+            }
+        };
+        let (method, target, version) = parse_request_line(&line)?;
+        let (raw_path, path, query) = parse_target(target)?;
+
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(reader, &mut budget)?
+                .ok_or(RequestError::BadRequest("connection closed in headers"))?;
+            if line.is_empty() {
+                break;
+            }
+            if headers.len() == limits.max_headers {
+                return Err(RequestError::HeadersTooLarge);
+            }
+            headers.push(parse_header(&line)?);
+        }
+
+        let mut request = Request {
+            method: method.to_string(),
+            path,
+            raw_path,
+            query,
+            version,
+            headers,
+            body: Vec::new(),
+            params: Vec::new(),
+        };
+        request.body = read_body(reader, &request, limits)?;
+        Ok(Some(request))
+    }
+
+    /// 헤더 값 (이름은 대소문자 구분 없음, 여러 개면 첫 번째)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 쿼리 파라미터 값 (여러 개면 첫 번째)
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 경로 파라미터 값 (`/users/:id`의 `id` 등)
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// 요청을 읽지 못한 이유
+#[derive(Debug)]
+pub enum RequestError {
+    Io(io::Error),            // 연결 에러 (응답할 수 없음)
+    BadRequest(&'static str), // 400: 형식이 잘못된 요청 (이유)
+    PayloadTooLarge,          // 413: 본문이 제한보다 큼
+    HeadersTooLarge,          // 431: 요청 라인과 헤더가 제한보다 큼
+}
+
+impl RequestError {
+    /// 응답할 상태 코드와 이유 문구 (연결 에러면 `None`)
+    pub fn status(&self) -> Option<(u16, &'static str)> {
+        match self {
+            RequestError::Io(_) => None,
+            RequestError::BadRequest(_) => Some((400, "Bad Request")),
+            RequestError::PayloadTooLarge => Some((413, "Content Too Large")),
+            RequestError::HeadersTooLarge => Some((431, "Request Header Fields Too Large")),
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RequestError::Io(err) => write!(f, "{err}"),
+            RequestError::BadRequest(reason) => write!(f, "bad request: {reason}"),
+            RequestError::PayloadTooLarge => write!(f, "request body too large"),
+            RequestError::HeadersTooLarge => write!(f, "request headers too large"),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+impl From<io::Error> for RequestError {
+    fn from(err: io::Error) -> RequestError {
+        RequestError::Io(err)
+    }
+}
+
+// `\r\n`(또는 `\n`)으로 끝나는 라인 하나를 줄바꿈 없이 읽음
+// 읽은 크기만큼 `budget`에서 빼며, 모자라면 431
+// 아무것도 읽기 전에 연결이 닫히면 `None`
+fn read_line(
+    reader: &mut impl BufRead,
+    budget: &mut usize,
+) -> Result<Option<Vec<u8>>, RequestError> {
+    let mut line = Vec::new();
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            if line.is_empty() {
+                return Ok(None);
+            }
+            return Err(RequestError::BadRequest(
+                "connection closed in the middle of a line",
+            ));
+        }
+
+        let (chunk, done) = match buf.iter().position(|&b| b == b'\n') {
+            Some(end) => (&buf[..=end], true),
+            None => (buf, false),
+        };
+        if chunk.len() > *budget {
+            return Err(RequestError::HeadersTooLarge);
+        }
+        *budget -= chunk.len();
+        line.extend_from_slice(chunk);
+        let len = chunk.len();
+        reader.consume(len);
+
+        if done {
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            return Ok(Some(line));
+        }
+    }
+}
+
+// `GET /path?query HTTP/1.1`
+fn parse_request_line(line: &[u8]) -> Result<(&str, &str, Version), RequestError> {
+    let line = std::str::from_utf8(line)
+        .map_err(|_| RequestError::BadRequest("request line is not UTF-8"))?;
+    let mut parts = line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(RequestError::BadRequest("malformed request line"));
+    };
+
+    if method.is_empty() || !method.bytes().all(is_token) {
+        return Err(RequestError::BadRequest("invalid method"));
+    }
+    let version = match version {
+        "HTTP/1.1" => Version::Http11,
+        "HTTP/1.0" => Version::Http10,
+        _ => return Err(RequestError::BadRequest("unsupported HTTP version")),
+    };
+    Ok((method, target, version))
+}
+
+// (받은 그대로의 경로, 퍼센트 인코딩을 푼 경로, 쿼리 파라미터)
+type Target = (String, String, Vec<(String, String)>);
+
+// 요청 대상을 경로와 쿼리 파라미터로 나눔
+// 서버 전체를 가리키는 `OPTIONS *`도 허용
+fn parse_target(target: &str) -> Result<Target, RequestError> {
+    if target == "*" {
+        return Ok((target.to_string(), target.to_string(), Vec::new()));
+    }
+    if !target.starts_with('/') {
+        return Err(RequestError::BadRequest(
+            "request target must start with '/'",
+        ));
+    }
+
+    let (raw_path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = percent_decode(raw_path, false)?;
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((percent_decode(name, true)?, percent_decode(value, true)?))
+        })
+        .collect::<Result<_, RequestError>>()?;
+    Ok((raw_path.to_string(), path, query))
+}
+
+// `%XX`를 바이트로 바꿈 (쿼리에서는 `+`도 공백)
+pub(crate) fn percent_decode(text: &str, plus_as_space: bool) -> Result<String, RequestError> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut input = text.bytes();
+    while let Some(b) = input.next() {
+        match b {
+            b'%' => {
+                let hex = [input.next(), input.next()];
+                let [Some(high), Some(low)] = hex.map(|digit| digit.and_then(hex_value)) else {
+                    return Err(RequestError::BadRequest("invalid percent-encoding"));
+                };
+                bytes.push(high << 4 | low);
+            }
+            b'+' if plus_as_space => bytes.push(b' '),
+            _ => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes)
+        .map_err(|_| RequestError::BadRequest("percent-encoded text is not UTF-8"))
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+// `Name: value`
+fn parse_header(line: &[u8]) -> Result<(String, String), RequestError> {
+    // 이전 헤더에 이어지는 줄 (obs-fold)은 더 이상 허용되지 않음 (RFC 9112 5.2)
+    if line.starts_with(b" ") || line.starts_with(b"\t") {
+        return Err(RequestError::BadRequest("obsolete header line folding"));
+    }
+    let line =
+        std::str::from_utf8(line).map_err(|_| RequestError::BadRequest("header is not UTF-8"))?;
+    let (name, value) = line
+        .split_once(':')
+        .ok_or(RequestError::BadRequest("header without ':'"))?;
+    // 이름과 `:` 사이의 공백도 허용하지 않음 (RFC 9112 5.1)
+    if name.is_empty() || !name.bytes().all(is_token) {
+        return Err(RequestError::BadRequest("invalid header name"));
+    }
+    Ok((
+        name.to_string(),
+        value.trim_matches([' ', '\t']).to_string(),
+    ))
+}
+
+// 메서드와 헤더 이름에 쓸 수 있는 문자 (RFC 9110 5.6.2)
+fn is_token(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+// 헤더에 따라 본문을 읽음
+fn read_body(
+    reader: &mut impl BufRead,
+    request: &Request,
+    limits: &Limits,
+) -> Result<Vec<u8>, RequestError> {
+    let transfer_encoding = request.header("Transfer-Encoding");
+    let content_length = content_length(request)?;
+
+    match (transfer_encoding, content_length) {
+        // 두 헤더가 함께 오면 어느 쪽이 맞는지 알 수 없어 요청 스머글링에 악용될 수 있음
+        (Some(_), Some(_)) => Err(RequestError::BadRequest(
+            "both Transfer-Encoding and Content-Length",
+        )),
+        (Some(encoding), None) => {
+            // 마지막 전송 코딩이 `chunked`가 아니면 본문의 끝을 알 수 없음
+            let last = encoding.rsplit(',').next().unwrap_or_default().trim();
+            if !last.eq_ignore_ascii_case("chunked") {
+                return Err(RequestError::BadRequest("unsupported Transfer-Encoding"));
+            }
+            read_chunked(reader, limits)
+        }
+        (None, Some(len)) => {
+            if len > limits.max_body_bytes {
+                return Err(RequestError::PayloadTooLarge);
+            }
+            let mut body = vec![0; len];
+            reader
+                .read_exact(&mut body)
+                .map_err(|err| match err.kind() {
+                    io::ErrorKind::UnexpectedEof => {
+                        RequestError::BadRequest("body shorter than Content-Length")
+                    }
+                    _ => RequestError::Io(err),
+                })?;
+            Ok(body)
+        }
+        (None, None) => Ok(Vec::new()),
+    }
+}
+
+// 같은 값이 여러 번 오는 것은 허용하지만 서로 다르면 400
+fn content_length(request: &Request) -> Result<Option<usize>, RequestError> {
+    let mut length = None;
+    let values = request
+        .headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Content-Length"))
+        .flat_map(|(_, value)| value.split(','));
+    for value in values {
+        let value = value.trim();
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(RequestError::BadRequest("invalid Content-Length"));
+        }
+        // 너무 커서 `usize`에 담기지 않는 값은 어차피 제한을 넘음
+        let value = value.parse().unwrap_or(usize::MAX);
+        if length.is_some_and(|length| length != value) {
+            return Err(RequestError::BadRequest("conflicting Content-Length"));
+        }
+        length = Some(value);
+    }
+    Ok(length)
+}
+
+// `크기(16진수)\r\n데이터\r\n` 반복 후 크기 0인 청크와 트레일러
+fn read_chunked(reader: &mut impl BufRead, limits: &Limits) -> Result<Vec<u8>, RequestError> {
+    let mut body = Vec::new();
+    // 청크 크기 라인과 트레일러도 헤더와 같은 제한을 받음
+    let mut budget = limits.max_header_bytes;
+
+    loop {
+        let line = read_line(reader, &mut budget)?.ok_or(RequestError::BadRequest(
+            "connection closed in chunked body",
+        ))?;
+        // 청크 확장(`;name=value`)은 무시
+        let size = line.split(|&b| b == b';').next().unwrap_or_default();
+        let size = std::str::from_utf8(size)
+            .ok()
+            .map(|size| size.trim_matches([' ', '\t']))
+            .filter(|size| !size.is_empty() && size.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or(RequestError::BadRequest("invalid chunk size"))?;
+        let size = usize::from_str_radix(size, 16).unwrap_or(usize::MAX);
+        if size == 0 {
+            break;
+        }
+        if size > limits.max_body_bytes - body.len() {
+            return Err(RequestError::PayloadTooLarge);
+        }
+
+        // 청크 데이터나 뒤의 CRLF 도중에 연결이 닫히면 잘못된 요청
+        let closed = |err: io::Error| match err.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                RequestError::BadRequest("connection closed in chunked body")
+            }
+            _ => RequestError::Io(err),
+        };
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..]).map_err(closed)?;
+        let mut crlf = [0; 2];
+        reader.read_exact(&mut crlf).map_err(closed)?;
+        if crlf != *b"\r\n" {
+            return Err(RequestError::BadRequest("chunk not followed by CRLF"));
+        }
+    }
+
+    // 트레일러 헤더는 읽고 버림
+    while !read_line(reader, &mut budget)?
+        .ok_or(RequestError::BadRequest("connection closed in trailers"))?
+        .is_empty()
+    {}
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Option<Request>, RequestError> {
+        Request::read(&mut input.as_bytes(), &Limits::default())
+    }
+
+    fn status(input: &str, limits: &Limits) -> u16 {
+        let err = Request::read(&mut input.as_bytes(), limits).unwrap_err();
+        err.status().unwrap().0
+    }
+
+    #[test]
+    fn request_line_query_and_headers() {
+        let request = parse(
+            "\r\nGET /search/hello%20world?q=rust+book&page=2&empty HTTP/1.1\r\n\
+             Host: localhost:7878\r\n\
+             Accept:text/html \r\n\r\n",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!("GET", request.method);
+        assert_eq!("/search/hello world", request.path);
+        assert_eq!("/search/hello%20world", request.raw_path);
+        assert_eq!(Some("rust book"), request.query("q"));
+        assert_eq!(Some("2"), request.query("page"));
+        assert_eq!(Some(""), request.query("empty"));
+        assert_eq!(Version::Http11, request.version);
+        assert_eq!(Some("localhost:7878"), request.header("host"));
+        assert_eq!(Some("text/html"), request.header("Accept"));
+        assert!(request.body.is_empty());
+
+        // 요청 없이 닫힌 연결
+        assert!(parse("").unwrap().is_none());
+    }
+
+    #[test]
+    fn bodies() {
+        let request = parse("POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello")
+            .unwrap()
+            .unwrap();
+        assert_eq!(b"hello", &request.body[..]);
+
+        let request = parse(
+            "POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+             5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\nExpires: never\r\n\r\n",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(b"hello, world", &request.body[..]);
+
+        // 같은 연결의 다음 요청은 본문 바로 뒤에서 시작
+        let mut input =
+            "POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\nokGET /next HTTP/1.1\r\n\r\n".as_bytes();
+        let limits = Limits::default();
+        Request::read(&mut input, &limits).unwrap().unwrap();
+        assert_eq!(
+            "/next",
+            Request::read(&mut input, &limits).unwrap().unwrap().path
+        );
+    }
+
+    #[test]
+    fn malformed_requests_are_400() {
+        let limits = Limits::default();
+        for input in [
+            "GET /\r\n\r\n",
+            "GET / HTTP/2.0\r\n\r\n",
+            "GET index.html HTTP/1.1\r\n\r\n",
+            "GET /%zz HTTP/1.1\r\n\r\n",
+            "G(T / HTTP/1.1\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost localhost\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost : localhost\r\n\r\n",
+            "GET / HTTP/1.1\r\nX-A: 1\r\n  continued\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost: localhost\r\n",
+            "POST / HTTP/1.1\r\nContent-Length: -1\r\n\r\n",
+            "POST / HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\nab",
+            "POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort",
+            "POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n",
+            "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n",
+            "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nxyz\r\n",
+            "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabcd\r\n0\r\n\r\n",
+            "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc",
+            "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r",
+        ] {
+            assert_eq!(400, status(input, &limits), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let limits = Limits {
+            max_header_bytes: 64,
+            max_headers: 2,
+            max_body_bytes: 4,
+            ..Limits::default()
+        };
+        let long_path = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(64));
+        assert_eq!(431, status(&long_path, &limits));
+        assert_eq!(
+            431,
+            status("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n", &limits)
+        );
+        assert_eq!(
+            413,
+            status("POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello", &limits)
+        );
+        assert_eq!(
+            413,
+            status(
+                "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n",
+                &limits
+            )
+        );
+
+        let request = Request::read(
+            &mut "POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nabcd".as_bytes(),
+            &limits,
+        );
+        assert_eq!(b"abcd", &request.unwrap().unwrap().body[..]);
+    }
+}
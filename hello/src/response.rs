@@ -0,0 +1,167 @@
+//! HTTP/1.1 응답
+use std::io::{self, Write};
+
+/// 보낼 응답
+/// `Content-Length`는 본문 길이로 자동으로 붙음
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// 본문이 없는 응답
+    pub fn new(status: u16) -> Response {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// `Content-Type`과 본문을 가진 응답
+    pub fn with_body(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> Response {
+        let mut response = Response::new(status);
+        response.set_header("Content-Type", content_type);
+        response.body = body.into();
+        response
+    }
+
+    pub fn html(status: u16, body: impl Into<Vec<u8>>) -> Response {
+        Response::with_body(status, "text/html; charset=utf-8", body)
+    }
+
+    pub fn text(status: u16, body: impl Into<Vec<u8>>) -> Response {
+        Response::with_body(status, "text/plain; charset=utf-8", body)
+    }
+
+    /// 헤더 값 (이름은 대소문자 구분 없음)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 같은 이름의 헤더가 있으면 값을 바꾸고, 없으면 추가
+    pub fn set_header(&mut self, name: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self
+            .headers
+            .iter_mut()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            Some((_, old)) => *old = value,
+            None => self.headers.push((name.to_string(), value)),
+        }
+    }
+
+    /// 상태 라인, 헤더, 본문을 씀
+    /// 버퍼를 가진 `out`이면 `flush`는 호출하는 쪽에서 (파이프라이닝된 응답을 모아서 보낼 수 있도록)
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        self.write(out, true)
+    }
+
+    /// `HEAD` 요청의 응답: 본문 없이 상태 라인과 헤더만 씀 (`Content-Length`는 본문 길이 그대로)
+    /// 본문을 만들지 않고 `Content-Length` 헤더를 직접 붙인 응답이면 그 값을 보냄
+    pub fn write_head_to(&self, out: &mut impl Write) -> io::Result<()> {
+        self.write(out, false)
+    }
+
+    fn write(&self, out: &mut impl Write, with_body: bool) -> io::Result<()> {
+        // 작은 조각으로 여러 번 보내지 않도록 한 번에 모아서 씀
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        // `Content-Length`를 직접 붙였더라도 본문을 보낼 때는 실제 본문 길이를 씀
+        let explicit_length = !with_body && self.header("Content-Length").is_some();
+        for (name, value) in &self.headers {
+            if name.eq_ignore_ascii_case("Content-Length") && !explicit_length {
+                continue;
+            }
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        // 1xx, 204, 304 응답에는 본문이 없으며 `Content-Length`도 보내지 않음
+        let bodiless = self.status < 200 || self.status == 204 || self.status == 304;
+        if !bodiless && !explicit_length {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        head.push_str("\r\n");
+
+        let mut bytes = head.into_bytes();
+        if with_body && !bodiless {
+            bytes.extend_from_slice(&self.body);
+        }
+        out.write_all(&bytes)
+    }
+}
+
+/// 상태 코드의 이유 문구
+pub fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Content Too Large",
+        416 => "Range Not Satisfiable",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_head_and_body() {
+        let mut response = Response::text(404, "missing\n");
+        response.set_header("content-type", "text/html");
+        response.set_header("Allow", "GET");
+
+        let mut out = Vec::new();
+        response.write_to(&mut out).unwrap();
+        assert_eq!(
+            "HTTP/1.1 404 Not Found\r\n\
+             Content-Type: text/html\r\n\
+             Allow: GET\r\n\
+             Content-Length: 8\r\n\r\n\
+             missing\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::new();
+        response.write_head_to(&mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .ends_with("Content-Length: 8\r\n\r\n"));
+
+        // 본문 없이 크기만 알리는 `HEAD` 응답
+        let mut response = Response::new(200);
+        response.set_header("Content-Length", "1024");
+        let mut out = Vec::new();
+        response.write_head_to(&mut out).unwrap();
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Length: 1024\r\n\r\n",
+            String::from_utf8(out).unwrap()
+        );
+        let mut out = Vec::new();
+        response.write_to(&mut out).unwrap();
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::new();
+        Response::new(304).write_to(&mut out).unwrap();
+        assert_eq!(b"HTTP/1.1 304 Not Modified\r\n\r\n", &out[..]);
+    }
+}
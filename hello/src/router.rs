@@ -0,0 +1,321 @@
+//! 메서드와 경로 패턴으로 요청을 핸들러에 연결
+//!
+//! 경로 패턴은 `/`로 나눈 조각 단위로 비교함
+//! - `users`: 같은 조각만 일치
+//! - `:id`: 조각 하나와 일치하고, `id` 파라미터로 추출
+//! - `*path`: 나머지 조각 전체(0개 이상)와 일치하고, `path` 파라미터로 추출 (패턴의 마지막에만 사용 가능)
+//!
+//! 조각은 퍼센트 인코딩을 풀기 전의 경로에서 나누므로 `%2F`는 조각을 나누지 않고,
+//! 각 조각과 파라미터 값은 나눈 뒤에 인코딩을 풂
+use crate::request::percent_decode;
+use crate::{Request, Response};
+use std::panic::{self, AssertUnwindSafe};
+
+/// 요청을 받아 응답을 만드는 함수
+/// 여러 스레드에서 동시에 호출되므로 `Send + Sync`
+pub type Handler = Box<dyn Fn(&Request) -> Response + Send + Sync>;
+
+/// 라우팅 테이블
+/// 등록한 순서대로 비교해 처음 일치하는 핸들러를 사용
+/// `HEAD` 요청은 `GET` 핸들러로도 처리됨 (본문은 연결 쪽에서 빼고 보냄)
+pub struct Router {
+    routes: Vec<Route>,
+    not_found: Handler,
+}
+
+struct Route {
+    method: String,
+    pattern: Vec<Segment>,
+    handler: Handler,
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Wildcard(String),
+}
+
+impl Router {
+    pub fn new() -> Router {
+        Router {
+            routes: Vec::new(),
+            not_found: Box::new(|_| Response::text(404, "Not Found\n")),
+        }
+    }
+
+    /// `method`로 `pattern`에 일치하는 경로를 요청하면 `handler` 호출
+    ///
+    /// # Panics
+    ///
+    /// 패턴이 `/`로 시작하지 않거나, `*` 조각이 마지막이 아니거나, 파라미터 이름이 비어 있으면 패닉
+    pub fn route<F>(&mut self, method: &str, pattern: &str, handler: F) -> &mut Router
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.routes.push(Route {
+            method: method.to_string(),
+            pattern: parse_pattern(pattern),
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    pub fn get<F>(&mut self, pattern: &str, handler: F) -> &mut Router
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.route("GET", pattern, handler)
+    }
+
+    pub fn post<F>(&mut self, pattern: &str, handler: F) -> &mut Router
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.route("POST", pattern, handler)
+    }
+
+    /// 일치하는 경로가 없을 때의 핸들러 (기본값은 `Not Found` 문구만 담은 404)
+    pub fn not_found<F>(&mut self, handler: F) -> &mut Router
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.not_found = Box::new(handler);
+        self
+    }
+
+    /// 요청에 맞는 핸들러를 찾아 호출
+    /// 추출한 경로 파라미터는 `request.params`에 채워짐
+    /// 경로는 일치하지만 메서드가 다르면 허용된 메서드를 `Allow` 헤더에 담은 405
+    /// 핸들러가 패닉하면 500으로 응답하고 연결을 닫음 (연결을 처리하던 작업 스레드는 유지)
+    pub fn handle(&self, request: &mut Request) -> Response {
+        let mut allowed: Vec<&str> = Vec::new();
+
+        for route in &self.routes {
+            let Some(params) = match_path(&route.pattern, &request.raw_path) else {
+                continue;
+            };
+            if route.method == request.method || (route.method == "GET" && request.method == "HEAD")
+            {
+                request.params = params;
+                return call(&route.handler, request);
+            }
+            let methods: &[&str] = match route.method.as_str() {
+                "GET" => &["GET", "HEAD"],
+                method => &[method],
+            };
+            for method in methods {
+                if !allowed.contains(method) {
+                    allowed.push(method);
+                }
+            }
+        }
+
+        request.params.clear();
+        if allowed.is_empty() {
+            return call(&self.not_found, request);
+        }
+        let mut response = Response::text(405, "Method Not Allowed\n");
+        response.set_header("Allow", allowed.join(", "));
+        response
+    }
+}
+
+impl Default for Router {
+    fn default() -> Router {
+        Router::new()
+    }
+}
+
+// 핸들러의 패닉은 응답 하나의 실패로 처리
+// (패닉 메시지는 기본 패닉 훅이 표준 에러에 출력)
+fn call(handler: &Handler, request: &Request) -> Response {
+    match panic::catch_unwind(AssertUnwindSafe(|| handler(request))) {
+        Ok(response) => response,
+        Err(_) => {
+            let mut response = Response::text(500, "Internal Server Error\n");
+            response.set_header("Connection", "close");
+            response
+        }
+    }
+}
+
+fn parse_pattern(pattern: &str) -> Vec<Segment> {
+    let rest = pattern
+        .strip_prefix('/')
+        .unwrap_or_else(|| panic!("route pattern must start with '/': {pattern}"));
+    let segments: Vec<Segment> = rest
+        .split('/')
+        .map(|segment| {
+            let named = |name: &str| {
+                assert!(
+                    !name.is_empty(),
+                    "unnamed parameter in route pattern: {pattern}"
+                );
+                name.to_string()
+            };
+            if let Some(name) = segment.strip_prefix(':') {
+                Segment::Param(named(name))
+            } else if let Some(name) = segment.strip_prefix('*') {
+                Segment::Wildcard(named(name))
+            } else {
+                Segment::Literal(segment.to_string())
+            }
+        })
+        .collect();
+
+    let wildcard = segments
+        .iter()
+        .position(|segment| matches!(segment, Segment::Wildcard(_)));
+    if let Some(position) = wildcard {
+        assert!(
+            position == segments.len() - 1,
+            "wildcard must be the last segment of a route pattern: {pattern}"
+        );
+    }
+    segments
+}
+
+// 받은 그대로의 경로 `path`가 일치하면 추출한 (이름, 인코딩을 푼 값) 목록
+fn match_path(pattern: &[Segment], path: &str) -> Option<Vec<(String, String)>> {
+    let mut rest = path.strip_prefix('/')?;
+    let mut params = Vec::new();
+
+    for (index, segment) in pattern.iter().enumerate() {
+        if let Segment::Wildcard(name) = segment {
+            params.push((name.clone(), decode(rest)));
+            return Some(params);
+        }
+
+        // 마지막 조각이면 남은 경로 전체, 아니면 다음 `/` 앞까지
+        let (part, next) = match rest.split_once('/') {
+            Some((part, next)) if index + 1 < pattern.len() => (part, next),
+            Some(_) => return None,
+            None if index + 1 == pattern.len() => (rest, ""),
+            // `/static` 요청과 `/static/*path` 패턴처럼 나머지가 모두 빈 와일드카드인 경우
+            None => match &pattern[index + 1..] {
+                [Segment::Wildcard(_)] => (rest, ""),
+                _ => return None,
+            },
+        };
+        match segment {
+            Segment::Literal(literal) if *literal == decode(part) => {}
+            Segment::Param(name) if !part.is_empty() => params.push((name.clone(), decode(part))),
+            _ => return None,
+        }
+        rest = next;
+    }
+    Some(params)
+}
+
+// 요청을 읽을 때 경로 전체의 인코딩을 이미 확인했으므로 조각 단위로도 실패하지 않음
+fn decode(part: &str) -> String {
+    percent_decode(part, false).unwrap_or_else(|_| part.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Limits;
+
+    fn request(method: &str, path: &str) -> Request {
+        let input = format!("{method} {path} HTTP/1.1\r\n\r\n");
+        let request = Request::read(&mut input.as_bytes(), &Limits::default());
+        request.unwrap().unwrap()
+    }
+
+    // 호출된 핸들러와 파라미터를 본문에 담는 라우터
+    fn router() -> Router {
+        let mut router = Router::new();
+        let describe = |name: &'static str| {
+            move |request: &Request| {
+                let params: Vec<String> = request
+                    .params
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect();
+                Response::text(200, format!("{name} {}", params.join(" ")))
+            }
+        };
+        router
+            .get("/", describe("index"))
+            .get("/users/me", describe("me"))
+            .get("/users/:id", describe("user"))
+            .route("DELETE", "/users/:id", describe("delete"))
+            .get("/users/:id/posts/:post", describe("post"))
+            .get("/static/*path", describe("static"));
+        router
+    }
+
+    fn body(router: &Router, method: &str, path: &str) -> String {
+        let response = router.handle(&mut request(method, path));
+        assert_eq!(200, response.status, "{method} {path}");
+        String::from_utf8(response.body).unwrap()
+    }
+
+    #[test]
+    fn dispatches_with_params() {
+        let router = router();
+        assert_eq!("index ", body(&router, "GET", "/"));
+        assert_eq!("me ", body(&router, "GET", "/users/me"));
+        assert_eq!("user id=42", body(&router, "GET", "/users/42?verbose=1"));
+        assert_eq!("delete id=42", body(&router, "DELETE", "/users/42"));
+        assert_eq!(
+            "post id=7 post=hello world",
+            body(&router, "GET", "/users/7/posts/hello%20world")
+        );
+        assert_eq!(
+            "static path=css/site.css",
+            body(&router, "GET", "/static/css/site.css")
+        );
+        assert_eq!("static path=", body(&router, "GET", "/static"));
+        assert_eq!("static path=", body(&router, "GET", "/static/"));
+
+        // 인코딩된 `/`는 파라미터 값의 일부
+        assert_eq!("user id=a/b", body(&router, "GET", "/users/a%2Fb"));
+        assert_eq!(
+            "post id=a/b post=c",
+            body(&router, "GET", "/users/a%2Fb/posts/c")
+        );
+        assert_eq!("me ", body(&router, "GET", "/users/%6De"));
+    }
+
+    #[test]
+    fn not_found_and_method_not_allowed() {
+        let mut router = router();
+        for path in ["/users", "/users/", "/users/42/posts", "/nothing"] {
+            assert_eq!(
+                404,
+                router.handle(&mut request("GET", path)).status,
+                "{path}"
+            );
+        }
+
+        let response = router.handle(&mut request("POST", "/users/42"));
+        assert_eq!(405, response.status);
+        assert_eq!(Some("GET, HEAD, DELETE"), response.header("Allow"));
+        assert_eq!(200, router.handle(&mut request("HEAD", "/users/42")).status);
+
+        router.not_found(|request| Response::text(404, format!("no {}", request.path)));
+        let response = router.handle(&mut request("GET", "/nothing"));
+        assert_eq!(b"no /nothing", &response.body[..]);
+    }
+
+    #[test]
+    fn handler_panic_is_500() {
+        let mut router = router();
+        router.get("/panic", |_| panic!("handler failed"));
+
+        let response = router.handle(&mut request("GET", "/panic"));
+        assert_eq!(500, response.status);
+        assert_eq!(Some("close"), response.header("Connection"));
+        // 같은 라우터로 다음 요청도 처리
+        assert_eq!("me ", body(&router, "GET", "/users/me"));
+    }
+
+    #[test]
+    #[should_panic(expected = "wildcard must be the last segment")]
+    fn wildcard_must_be_last() {
+        Router::new().get("/files/*path/raw", |_| Response::new(200));
+    }
+}
@@ -0,0 +1,313 @@
+//! 연결 수신부터 종료까지 서버 전체의 생명 주기
+//!
+//! 종료를 요청하면 (`ServerHandle::shutdown`, 또는 `main`에서 연결한 SIGINT/SIGTERM)
+//! 1. 새 연결을 더 이상 받지 않음
+//! 2. 처리 중인 요청은 끝까지 응답하고 (`Connection: close`), 다음 요청을 기다리던 연결은 닫음
+//! 3. `drain_timeout`이 지나도 끝나지 않은 연결은 강제로 닫은 뒤 작업 스레드를 모두 `join`
+//!
+//! 작업 스레드를 기다리는 연결이 있으면, 다음 요청을 기다리던 keep-alive 연결은 `idle_timeout`까지
+//! 기다리지 않고 닫아 작업 스레드를 넘겨줌
+use crate::connection::serve_connection;
+use crate::pool::Workers;
+use crate::{Limits, Router};
+use std::collections::HashMap;
+use std::io;
+use std::net::{
+    IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs,
+};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 연결을 받아 스레드 풀에서 처리하는 서버
+pub struct Server {
+    listener: TcpListener,
+    router: Arc<Router>,
+    handle: ServerHandle,
+    pub workers: usize, // 작업 스레드 수 (동시에 처리할 수 있는 연결 수, 기본값은 `default_workers`)
+    pub limits: Limits, // 요청과 연결의 제한
+    pub drain_timeout: Duration, // 종료 요청 후 처리 중인 연결을 기다리는 최대 시간
+}
+
+impl Server {
+    /// `address`에서 연결 수신 대기 (포트 0이면 사용 가능한 포트를 골라 사용)
+    pub fn bind(address: impl ToSocketAddrs, router: Router) -> io::Result<Server> {
+        let listener = TcpListener::bind(address)?;
+        let handle = ServerHandle {
+            stopping: Arc::new(AtomicBool::new(false)),
+            address: listener.local_addr()?,
+        };
+
+        Ok(Server {
+            listener,
+            router: Arc::new(router),
+            handle,
+            workers: default_workers(),
+            limits: Limits::default(),
+            drain_timeout: Duration::from_secs(10),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.handle.address
+    }
+
+    /// 다른 스레드에서 서버를 종료할 때 사용
+    pub fn handle(&self) -> ServerHandle {
+        self.handle.clone()
+    }
+
+    /// 종료를 요청받을 때까지 연결을 처리하고, 모든 작업 스레드가 끝난 뒤 반환
+    pub fn run(self) -> io::Result<()> {
+        let pool = Workers::new(self.workers);
+        let connections = Arc::new(Connections::default());
+        let limits = Arc::new(self.limits);
+        let stopping = &self.handle.stopping;
+
+        for stream in self.listener.incoming() {
+            // 종료 요청이 `accept`를 깨우려고 만든 연결이거나, 그 사이에 들어온 연결
+            if stopping.load(Ordering::SeqCst) {
+                break;
+            }
+            let stream = match stream {
+                Ok(stream) => stream,
+                // 연결 하나의 에러 (예: 동시 연결 수 제한)로 서버 전체를 종료하지 않음
+                Err(err) => {
+                    eprintln!("Accept error: {err}");
+                    continue;
+                }
+            };
+
+            // 스레드 풀에서 기다리는 연결도 종료 시 기다릴 수 있도록 받자마자 등록
+            let registration = match Connections::register(&connections, &stream) {
+                Ok(registration) => registration,
+                Err(err) => {
+                    eprintln!("Accept error: {err}");
+                    continue;
+                }
+            };
+            let router = Arc::clone(&self.router);
+            let limits = Arc::clone(&limits);
+            let stopping = Arc::clone(stopping);
+            pool.execute(move || {
+                let waiting = &registration.connections.waiting;
+                waiting.fetch_sub(1, Ordering::SeqCst);
+                if let Err(err) = serve_connection(stream, &router, &limits, &stopping, waiting) {
+                    eprintln!("Connection error: {err}");
+                }
+            });
+        }
+
+        // 수신 소켓을 닫아 새 연결은 바로 거부
+        drop(self.listener);
+        connections.drain(self.drain_timeout);
+        // `Workers`의 `drop`에서 작업 스레드를 모두 `join`
+        drop(pool);
+        Ok(())
+    }
+}
+
+/// CPU 하나당 작업 스레드 수
+/// 작업 스레드는 연결이 유지되는 동안 소켓 읽기와 쓰기에서 대부분 블록되어 CPU를 거의 쓰지 않으므로,
+/// CPU 수만큼만 두면 느린 클라이언트 몇 개가 모든 스레드를 차지함
+/// 4배면 CPU를 놀리지 않으면서도 스레드가 지나치게 많아지지 않고,
+/// 그래도 모자라면 다음 요청을 기다리던 연결이 작업 스레드를 양보함 (`connection` 참고)
+pub const WORKERS_PER_CPU: usize = 4;
+
+/// 작업 스레드 수의 기본값: CPU 수 × `WORKERS_PER_CPU` (CPU 수를 알 수 없으면 하나로 봄)
+/// `Server::workers`나 `main`의 `WORKERS` 환경변수로 바꿀 수 있음
+pub fn default_workers() -> usize {
+    thread::available_parallelism().map_or(1, |count| count.get()) * WORKERS_PER_CPU
+}
+
+/// 실행 중인 서버의 종료 요청 (복제해서 여러 곳에서 사용 가능)
+#[derive(Clone)]
+pub struct ServerHandle {
+    stopping: Arc<AtomicBool>,
+    address: SocketAddr,
+}
+
+impl ServerHandle {
+    /// 종료 요청 (실제 종료를 기다리지는 않음, `Server::run`이 반환하면 종료된 것)
+    pub fn shutdown(&self) {
+        if self.stopping.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // `accept`에서 블록된 수신 스레드를 깨우기 위해 직접 연결
+        // 모든 주소(`0.0.0.0`)에서 대기 중이면 같은 포트의 루프백 주소로 연결
+        let mut address = self.address;
+        if address.ip().is_unspecified() {
+            address.set_ip(match address.ip() {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            });
+        }
+        let _ = TcpStream::connect_timeout(&address, Duration::from_secs(1));
+    }
+}
+
+/// 처리 중이거나 스레드 풀에서 기다리는 연결 목록
+#[derive(Default)]
+struct Connections {
+    streams: Mutex<HashMap<u64, TcpStream>>, // 강제로 닫을 때 사용할 복제본
+    next_id: AtomicU64,
+    waiting: AtomicUsize, // 스레드 풀에서 작업 스레드를 기다리는 연결 수
+    finished: Condvar,    // 연결이 끝날 때마다 알림
+}
+
+/// 연결 하나의 등록 (`drop`되면 목록에서 제거)
+struct Registration {
+    connections: Arc<Connections>,
+    id: u64,
+}
+
+impl Connections {
+    fn register(connections: &Arc<Connections>, stream: &TcpStream) -> io::Result<Registration> {
+        let id = connections.next_id.fetch_add(1, Ordering::Relaxed);
+        let stream = stream.try_clone()?;
+        connections.streams.lock().unwrap().insert(id, stream);
+        connections.waiting.fetch_add(1, Ordering::SeqCst);
+
+        Ok(Registration {
+            connections: Arc::clone(connections),
+            id,
+        })
+    }
+
+    // 모든 연결이 끝나길 기다리고, `timeout`이 지나면 남은 연결을 강제로 닫음
+    fn drain(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let mut streams = self.streams.lock().unwrap();
+        while !streams.is_empty() {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                eprintln!(
+                    "Closing {} connection(s) after drain timeout",
+                    streams.len()
+                );
+                // 블록된 읽기와 쓰기가 에러를 반환하므로 작업 스레드가 곧 끝남
+                for stream in streams.values() {
+                    let _ = stream.shutdown(Shutdown::Both);
+                }
+                return;
+            }
+            streams = self.finished.wait_timeout(streams, left).unwrap().0;
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.connections.streams.lock().unwrap().remove(&self.id);
+        self.connections.finished.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Response;
+    use std::io::{Read, Write};
+
+    // `/sleep/:ms`는 지정한 시간 뒤에 응답
+    fn start(drain_timeout: Duration) -> (ServerHandle, SocketAddr, thread::JoinHandle<()>) {
+        start_with(|server| server.drain_timeout = drain_timeout)
+    }
+
+    fn start_with(
+        configure: impl FnOnce(&mut Server),
+    ) -> (ServerHandle, SocketAddr, thread::JoinHandle<()>) {
+        let mut router = Router::new();
+        router.get("/sleep/:ms", |request| {
+            let ms = request.param("ms").unwrap().parse().unwrap();
+            thread::sleep(Duration::from_millis(ms));
+            Response::text(200, "slept")
+        });
+        let mut server = Server::bind("127.0.0.1:0", router).unwrap();
+        configure(&mut server);
+
+        let (handle, address) = (server.handle(), server.local_addr());
+        let running = thread::spawn(move || server.run().unwrap());
+        (handle, address, running)
+    }
+
+    fn send(address: SocketAddr, request: &str) -> TcpStream {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        stream
+    }
+
+    fn read_all(stream: &mut TcpStream) -> String {
+        let mut output = Vec::new();
+        // 강제로 닫힌 연결은 RST로 끝날 수 있음
+        let _ = stream.read_to_end(&mut output);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn finishes_in_flight_requests() {
+        let (handle, address, running) = start(Duration::from_secs(10));
+
+        // 응답을 받은 뒤 다음 요청을 기다리는 연결
+        let mut idle = send(address, "GET /sleep/0 HTTP/1.1\r\n\r\n");
+        let mut response = [0; 1024];
+        assert!(idle.read(&mut response).unwrap() > 0);
+        // 처리 중인 요청
+        let mut busy = send(address, "GET /sleep/300 HTTP/1.1\r\n\r\n");
+        thread::sleep(Duration::from_millis(100));
+
+        handle.shutdown();
+
+        let output = read_all(&mut busy);
+        assert!(output.starts_with("HTTP/1.1 200 OK"), "{output}");
+        assert!(output.contains("Connection: close"));
+        assert!(output.ends_with("slept"));
+        assert_eq!("", read_all(&mut idle));
+        drop((busy, idle));
+
+        running.join().unwrap();
+        assert!(TcpStream::connect(address).is_err());
+    }
+
+    #[test]
+    fn idle_connections_yield_to_waiting_ones() {
+        let (handle, address, running) = start_with(|server| {
+            server.workers = 1;
+            server.limits.idle_timeout = Duration::from_secs(10);
+        });
+
+        // 하나뿐인 작업 스레드를 차지한 채 다음 요청을 기다리는 연결
+        let mut idle = send(address, "GET /sleep/0 HTTP/1.1\r\n\r\n");
+        let mut response = [0; 1024];
+        assert!(idle.read(&mut response).unwrap() > 0);
+
+        // `idle_timeout`까지 기다리지 않고 처리됨
+        let started = Instant::now();
+        let mut next = send(
+            address,
+            "GET /sleep/0 HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+        assert!(read_all(&mut next).ends_with("slept"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!("", read_all(&mut idle));
+        drop((next, idle));
+
+        handle.shutdown();
+        running.join().unwrap();
+    }
+
+    #[test]
+    fn drain_timeout_closes_slow_connections() {
+        let (handle, address, running) = start(Duration::from_millis(50));
+        let mut slow = send(address, "GET /sleep/500 HTTP/1.1\r\n\r\n");
+        thread::sleep(Duration::from_millis(100));
+
+        let started = Instant::now();
+        handle.shutdown();
+        // 연결은 제한 시간에 닫히고, 작업 스레드는 핸들러가 끝난 뒤 `join`됨
+        assert!(!read_all(&mut slow).contains("slept"));
+        assert!(started.elapsed() < Duration::from_millis(400));
+        running.join().unwrap();
+    }
+}
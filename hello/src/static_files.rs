@@ -0,0 +1,584 @@
+//! 디렉터리의 파일을 그대로 응답하는 핸들러
+//!
+//! - 루트 밖의 파일은 응답하지 않음 (`..` 조각, 루트 밖을 가리키는 심볼릭 링크)
+//! - 확장자로 `Content-Type` 결정
+//! - `ETag`/`Last-Modified`를 붙이고, 조건부 요청(`If-None-Match`, `If-Modified-Since`)에는 304
+//! - `Range` 요청에는 206 (범위 하나만 지원, 여러 범위는 전체 응답), 파일에서는 요청한 범위만 읽음
+//! - `HEAD`와 304 응답은 파일을 읽지 않고 메타데이터로만 만듦
+//! - 디렉터리는 `index.html`이 있으면 그 파일, 없으면 파일 목록 페이지
+use crate::{Request, Response};
+use std::fmt::Write as _;
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// `root` 디렉터리 아래의 파일 응답
+pub struct StaticFiles {
+    root: PathBuf,
+    pub listing: bool, // `index.html`이 없는 디렉터리의 파일 목록 페이지 사용 여부
+}
+
+impl StaticFiles {
+    pub fn new(root: impl Into<PathBuf>) -> StaticFiles {
+        StaticFiles {
+            root: root.into(),
+            listing: true,
+        }
+    }
+
+    /// 루트 기준 상대 경로 `path`의 파일로 응답
+    /// 보통 `/static/*path` 같은 라우트의 와일드카드 파라미터를 넘김
+    pub fn serve(&self, request: &Request, path: &str) -> Response {
+        let Some(file) = self.resolve(path) else {
+            return Response::text(404, "Not Found\n");
+        };
+        let metadata = match fs::metadata(&file) {
+            Ok(metadata) => metadata,
+            Err(_) => return Response::text(404, "Not Found\n"),
+        };
+        if !metadata.is_dir() {
+            return serve_file(request, &file, &metadata);
+        }
+
+        // 목록 페이지의 상대 링크가 디렉터리 안을 가리키도록 `/`로 끝나는 주소로 이동
+        if !request.path.ends_with('/') {
+            let mut response = Response::new(301);
+            response.set_header("Location", format!("{}/", encode_path(&request.path)));
+            return response;
+        }
+        let index = file.join("index.html");
+        match fs::metadata(&index) {
+            Ok(metadata) if metadata.is_file() => serve_file(request, &index, &metadata),
+            _ if self.listing => listing(&request.path, &file),
+            _ => Response::text(404, "Not Found\n"),
+        }
+    }
+
+    // 루트 안의 실제 경로 (루트 밖이거나 없는 파일이면 `None`)
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let mut file = self.root.clone();
+        for segment in path.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => return None,
+                // 다른 플랫폼의 경로 구분자나 NUL이 들어간 조각
+                _ if segment.contains(['\\', '\0']) => return None,
+                _ => file.push(segment),
+            }
+        }
+
+        // 심볼릭 링크를 모두 따라간 경로가 루트 안에 있는지 확인
+        let root = self.root.canonicalize().ok()?;
+        let file = file.canonicalize().ok()?;
+        file.starts_with(root).then_some(file)
+    }
+}
+
+fn serve_file(request: &Request, file: &Path, metadata: &Metadata) -> Response {
+    let modified = metadata.modified().ok();
+    let etag = etag(metadata.len(), modified);
+    let validators = |response: &mut Response| {
+        response.set_header("ETag", etag.clone());
+        if let Some(modified) = modified {
+            response.set_header("Last-Modified", http_date(modified));
+        }
+    };
+
+    if not_modified(request, &etag, modified) {
+        let mut response = Response::new(304);
+        validators(&mut response);
+        return response;
+    }
+
+    let len = metadata.len() as usize;
+    let (status, range) = match requested_range(request, &etag, modified, len) {
+        Ranged::Full => (200, 0..len),
+        Ranged::Partial(range) => (206, range),
+        Ranged::Unsatisfiable => {
+            let mut response = Response::new(416);
+            response.set_header("Content-Range", format!("bytes */{len}"));
+            validators(&mut response);
+            response.set_header("Accept-Ranges", "bytes");
+            return response;
+        }
+    };
+
+    let Ok(mut opened) = File::open(file) else {
+        return Response::text(403, "Forbidden\n");
+    };
+    // `HEAD`는 본문을 보내지 않으므로 파일을 읽지 않고 크기만 알림
+    let mut response = if request.method == "HEAD" {
+        let mut response = Response::with_body(status, content_type(file), Vec::new());
+        response.set_header("Content-Length", range.len().to_string());
+        response
+    } else {
+        match read_range(&mut opened, &range) {
+            Ok(contents) => Response::with_body(status, content_type(file), contents),
+            Err(_) => return Response::text(403, "Forbidden\n"),
+        }
+    };
+    if status == 206 {
+        let content_range = format!("bytes {}-{}/{len}", range.start, range.end - 1);
+        response.set_header("Content-Range", content_range);
+    }
+    validators(&mut response);
+    response.set_header("Accept-Ranges", "bytes");
+    response
+}
+
+// 파일에서 `range` 부분만 읽음 (큰 파일의 일부를 요청해도 전체를 읽지 않도록)
+fn read_range(file: &mut File, range: &Range<usize>) -> io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(range.start as u64))?;
+    let mut contents = Vec::with_capacity(range.len());
+    file.take(range.len() as u64).read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+// 크기와 수정 시각이 같으면 같은 내용으로 봄
+fn etag(len: u64, modified: Option<SystemTime>) -> String {
+    let nanos = modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos());
+    format!("\"{len:x}-{nanos:x}\"")
+}
+
+// `If-None-Match`가 있으면 그것만, 없으면 `If-Modified-Since`로 판단 (RFC 9110 13.2.2)
+fn not_modified(request: &Request, etag: &str, modified: Option<SystemTime>) -> bool {
+    if let Some(tags) = request.header("If-None-Match") {
+        // 약한 비교: `W/` 접두사는 무시
+        return tags
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+    }
+    match (
+        request
+            .header("If-Modified-Since")
+            .and_then(parse_http_date),
+        modified,
+    ) {
+        // HTTP 날짜는 초 단위이므로 초 미만은 버리고 비교
+        (Some(since), Some(modified)) => truncate_to_seconds(modified) <= since,
+        _ => false,
+    }
+}
+
+enum Ranged {
+    Full,
+    Partial(Range<usize>),
+    Unsatisfiable,
+}
+
+// `Range: bytes=시작-끝`, `bytes=시작-`, `bytes=-마지막N바이트`
+// 해석할 수 없는 값은 무시하고 전체를 응답 (RFC 9110 14.2)
+fn requested_range(
+    request: &Request,
+    etag: &str,
+    modified: Option<SystemTime>,
+    len: usize,
+) -> Ranged {
+    let Some(value) = request.header("Range") else {
+        return Ranged::Full;
+    };
+    // `If-Range`의 값이 현재 파일과 다르면 바뀐 파일이므로 전체를 응답
+    if let Some(condition) = request.header("If-Range") {
+        let same_date = modified.is_some_and(|modified| http_date(modified) == condition);
+        if condition != etag && !same_date {
+            return Ranged::Full;
+        }
+    }
+
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ranged::Full;
+    };
+    let Some((start, end)) = spec.split_once('-').filter(|_| !spec.contains(',')) else {
+        return Ranged::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = if start.is_empty() {
+        match end.parse::<usize>() {
+            Ok(0) => return Ranged::Unsatisfiable,
+            Ok(suffix) => len.saturating_sub(suffix)..len,
+            Err(_) => return Ranged::Full,
+        }
+    } else {
+        let Ok(start) = start.parse::<usize>() else {
+            return Ranged::Full;
+        };
+        let end = match end {
+            "" => len,
+            end => match end.parse::<usize>() {
+                Ok(end) if end >= start => end.saturating_add(1).min(len),
+                _ => return Ranged::Full,
+            },
+        };
+        start..end
+    };
+
+    if range.start >= len {
+        Ranged::Unsatisfiable
+    } else {
+        Ranged::Partial(range)
+    }
+}
+
+// 확장자로 판단한 MIME 타입
+fn content_type(file: &Path) -> &'static str {
+    let extension = file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "md" | "rs" | "toml" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        _ => "application/octet-stream",
+    }
+}
+
+// 디렉터리의 파일 목록 페이지 (하위 디렉터리는 `/`를 붙여 먼저 표시)
+fn listing(url: &str, dir: &Path) -> Response {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Response::text(403, "Forbidden\n");
+    };
+    let mut names: Vec<(bool, String)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let is_dir = entry.file_type().ok()?.is_dir();
+            Some((!is_dir, entry.file_name().to_string_lossy().into_owned()))
+        })
+        .collect();
+    names.sort();
+
+    let title = escape_html(url);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n  <head>\n    <meta charset=\"utf-8\">\n    \
+         <title>Index of {title}</title>\n  </head>\n  <body>\n    <h1>Index of {title}</h1>\n    <ul>\n"
+    );
+    if url != "/" {
+        html.push_str("      <li><a href=\"../\">../</a></li>\n");
+    }
+    for (is_file, name) in names {
+        let slash = if is_file { "" } else { "/" };
+        let _ = writeln!(
+            html,
+            "      <li><a href=\"{}{slash}\">{}{slash}</a></li>",
+            encode_path(&name),
+            escape_html(&name)
+        );
+    }
+    html.push_str("    </ul>\n  </body>\n</html>\n");
+    Response::html(200, html)
+}
+
+// URL 경로에 그대로 쓸 수 없는 바이트를 `%XX`로 바꿈 (`/`는 유지)
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            let _ = write!(encoded, "%{b:02X}");
+        }
+    }
+    encoded
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"]; // 1970-01-01은 목요일
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+// `Sun, 06 Nov 1994 08:49:37 GMT` (IMF-fixdate)
+fn http_date(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{}, {day:02} {} {year} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(days % 7) as usize],
+        MONTHS[month as usize - 1],
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// IMF-fixdate만 지원 (더 이상 쓰이지 않는 RFC 850, asctime 형식은 `None`)
+fn parse_http_date(text: &str) -> Option<SystemTime> {
+    let mut parts = text.split_whitespace();
+    let (_weekday, day, month, year, time, zone) = (
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+        parts.next()?,
+    );
+    if zone != "GMT" || parts.next().is_some() {
+        return None;
+    }
+    let day: u32 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|name| *name == month)? as u32 + 1;
+    let year: i64 = year.parse().ok()?;
+    let mut time = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+// 1970-01-01부터의 일 수 <-> 그레고리력 날짜 (Howard Hinnant의 알고리즘)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Limits;
+
+    fn request(path: &str, headers: &[(&str, &str)]) -> Request {
+        let mut input = format!("GET {path} HTTP/1.1\r\n");
+        for (name, value) in headers {
+            input.push_str(&format!("{name}: {value}\r\n"));
+        }
+        input.push_str("\r\n");
+        Request::read(&mut input.as_bytes(), &Limits::default())
+            .unwrap()
+            .unwrap()
+    }
+
+    // `/static/*path`에 연결된 것처럼 요청
+    fn get(files: &StaticFiles, path: &str, headers: &[(&str, &str)]) -> Response {
+        let request = request(&format!("/static/{}", encode_path(path)), headers);
+        files.serve(&request, path)
+    }
+
+    fn site() -> (tempfile::TempDir, StaticFiles) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("site/docs")).unwrap();
+        fs::write(dir.path().join("site/app.css"), "body {}").unwrap();
+        fs::write(
+            dir.path().join("site/logo.png"),
+            [0x89, b'P', b'N', b'G', 0, 0xff],
+        )
+        .unwrap();
+        fs::write(dir.path().join("site/docs/a <b>.txt"), "0123456789").unwrap();
+        fs::write(dir.path().join("secret.txt"), "password").unwrap();
+        let files = StaticFiles::new(dir.path().join("site"));
+        (dir, files)
+    }
+
+    #[test]
+    fn serves_files_with_types() {
+        let (_dir, files) = site();
+
+        let response = get(&files, "app.css", &[]);
+        assert_eq!(200, response.status);
+        assert_eq!(
+            Some("text/css; charset=utf-8"),
+            response.header("Content-Type")
+        );
+        assert_eq!(b"body {}", &response.body[..]);
+
+        let response = get(&files, "logo.png", &[]);
+        assert_eq!(Some("image/png"), response.header("Content-Type"));
+        assert_eq!(&[0x89, b'P', b'N', b'G', 0, 0xff], &response.body[..]);
+
+        for path in [
+            "missing.txt",
+            "../secret.txt",
+            "docs/../../secret.txt",
+            "docs\\..\\..\\secret.txt",
+        ] {
+            assert_eq!(404, get(&files, path, &[]).status, "{path}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_outside_root() {
+        let (dir, files) = site();
+        std::os::unix::fs::symlink(
+            dir.path().join("secret.txt"),
+            dir.path().join("site/link.txt"),
+        )
+        .unwrap();
+        assert_eq!(404, get(&files, "link.txt", &[]).status);
+    }
+
+    #[test]
+    fn conditional_requests() {
+        let (_dir, files) = site();
+        let response = get(&files, "app.css", &[]);
+        let etag = response.header("ETag").unwrap().to_string();
+        let modified = response.header("Last-Modified").unwrap().to_string();
+
+        let response = get(&files, "app.css", &[("If-None-Match", &etag)]);
+        assert_eq!(304, response.status);
+        assert!(response.body.is_empty());
+        assert_eq!(Some(etag.as_str()), response.header("ETag"));
+        assert_eq!(
+            304,
+            get(
+                &files,
+                "app.css",
+                &[("If-None-Match", &format!("\"x\", W/{etag}"))]
+            )
+            .status
+        );
+        assert_eq!(
+            200,
+            get(&files, "app.css", &[("If-None-Match", "\"other\"")]).status
+        );
+
+        assert_eq!(
+            304,
+            get(&files, "app.css", &[("If-Modified-Since", &modified)]).status
+        );
+        let earlier = "Sun, 06 Nov 1994 08:49:37 GMT";
+        assert_eq!(
+            200,
+            get(&files, "app.css", &[("If-Modified-Since", earlier)]).status
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        let (_dir, files) = site();
+        let path = "docs/a <b>.txt";
+        let range = |value: &str| get(&files, path, &[("Range", value)]);
+
+        let response = range("bytes=2-4");
+        assert_eq!(206, response.status);
+        assert_eq!(b"234", &response.body[..]);
+        assert_eq!(Some("bytes 2-4/10"), response.header("Content-Range"));
+        assert_eq!(b"789", &range("bytes=7-").body[..]);
+        assert_eq!(b"6789", &range("bytes=-4").body[..]);
+        assert_eq!(b"89", &range("bytes=8-100").body[..]);
+
+        let response = range("bytes=10-");
+        assert_eq!(416, response.status);
+        assert_eq!(Some("bytes */10"), response.header("Content-Range"));
+        for ignored in ["bytes=5-2", "bytes=0-1,4-5", "lines=1-2", "bytes=x-"] {
+            assert_eq!(200, range(ignored).status, "{ignored}");
+        }
+
+        // `HEAD`는 본문 없이 보낼 크기만
+        let mut request = request("/static/docs/a%20%3Cb%3E.txt", &[("Range", "bytes=2-4")]);
+        request.method = "HEAD".to_string();
+        let response = files.serve(&request, path);
+        assert_eq!(206, response.status);
+        assert!(response.body.is_empty());
+        assert_eq!(Some("3"), response.header("Content-Length"));
+        assert_eq!(Some("bytes 2-4/10"), response.header("Content-Range"));
+
+        // 바뀐 파일이면 범위 대신 전체
+        let stale = get(
+            &files,
+            path,
+            &[("Range", "bytes=0-0"), ("If-Range", "\"stale\"")],
+        );
+        assert_eq!(200, stale.status);
+    }
+
+    #[test]
+    fn directories() {
+        let (dir, files) = site();
+
+        let response = get(&files, "docs", &[]);
+        assert_eq!(301, response.status);
+        assert_eq!(Some("/static/docs/"), response.header("Location"));
+
+        let response = get(&files, "", &[]);
+        let html = String::from_utf8(response.body).unwrap();
+        assert!(html.contains("<title>Index of /static/</title>"));
+        assert!(html.find("docs/").unwrap() < html.find("app.css").unwrap());
+        let html = String::from_utf8(get(&files, "docs/", &[]).body).unwrap();
+        assert!(html.contains(r#"<a href="a%20%3Cb%3E.txt">a &lt;b&gt;.txt</a>"#));
+
+        fs::write(dir.path().join("site/docs/index.html"), "<h1>Docs</h1>").unwrap();
+        let response = get(&files, "docs/", &[]);
+        assert_eq!(
+            Some("text/html; charset=utf-8"),
+            response.header("Content-Type")
+        );
+        assert_eq!(b"<h1>Docs</h1>", &response.body[..]);
+    }
+
+    #[test]
+    fn http_dates() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!("Sun, 06 Nov 1994 08:49:37 GMT", http_date(time));
+        assert_eq!(Some(time), parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"));
+        assert_eq!(
+            "Thu, 29 Feb 2024 00:00:00 GMT",
+            http_date(UNIX_EPOCH + Duration::from_secs(1709164800))
+        );
+        assert_eq!(None, parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"));
+    }
+}
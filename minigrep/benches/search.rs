@@ -0,0 +1,74 @@
+//! 문자열 검색 성능 비교
+//!
+//! `cargo bench`로 실행하며, 블록 단위 문자열 검색을
+//! 라인마다 검색어를 확인하는 방식(기존 `search`, 정규 표현식 검색기)과 비교함
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use minigrep::{find_matches, Config, PatternMatcher, Settings};
+use std::hint::black_box;
+
+// 일치 라인이 드문 약 8MB의 입력
+fn haystack() -> String {
+    let poem = include_str!("../poem.txt");
+    let mut contents = String::new();
+    let mut i = 0;
+
+    while contents.len() < 8 * 1024 * 1024 {
+        for line in poem.lines() {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        if i % 1000 == 0 {
+            contents.push_str("Rust: safe, fast, productive. Pick three.\n");
+        }
+        i += 1;
+    }
+
+    contents
+}
+
+fn matcher(args: &[&str]) -> PatternMatcher {
+    let mut args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    args.insert(0, "minigrep".to_string());
+    // 설정 파일이나 `IGNORE_CASE`에 따라 측정 대상이 달라지지 않도록 기본 설정 사용
+    PatternMatcher::new(&Config::build_with(&args, &Settings::default()).unwrap()).unwrap()
+}
+
+fn single_literal(c: &mut Criterion) {
+    let contents = haystack();
+    let literal = matcher(&["productive"]);
+    // `--regex`이면 블록 검색을 사용하지 않고 라인마다 확인함
+    let line_by_line = matcher(&["--regex", "productive"]);
+
+    let mut group = c.benchmark_group("single_literal");
+    group.throughput(Throughput::Bytes(contents.len() as u64));
+    group.bench_function("legacy_search", |b| {
+        b.iter(|| minigrep::search(black_box("productive"), black_box(&contents)).len())
+    });
+    group.bench_function("line_by_line", |b| {
+        b.iter(|| find_matches(&line_by_line, black_box(contents.as_bytes())).len())
+    });
+    group.bench_function("block_scan", |b| {
+        b.iter(|| find_matches(&literal, black_box(contents.as_bytes())).len())
+    });
+    group.finish();
+}
+
+fn multiple_literals(c: &mut Criterion) {
+    let contents = haystack();
+    let args = ["-e", "productive", "-e", "Pick four", "-e", "nobody!!"];
+    let literals = matcher(&args);
+    let line_by_line = matcher(&[&["--regex"], &args[..]].concat());
+
+    let mut group = c.benchmark_group("multiple_literals");
+    group.throughput(Throughput::Bytes(contents.len() as u64));
+    group.bench_function("line_by_line", |b| {
+        b.iter(|| find_matches(&line_by_line, black_box(contents.as_bytes())).len())
+    });
+    group.bench_function("block_scan", |b| {
+        b.iter(|| find_matches(&literals, black_box(contents.as_bytes())).len())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, single_literal, multiple_literals);
+criterion_main!(benches);
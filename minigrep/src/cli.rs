@@ -0,0 +1,272 @@
+//! 명령줄 인수 정의
+//!
+//! `clap`의 derive 매크로로 옵션을 선언하면 파싱, `--help`, `--version` 출력이 자동으로 생성됨
+use crate::color::ColorChoice;
+use crate::fold::CaseFold;
+use clap::{Arg, CommandFactory, Parser, Subcommand};
+use std::error::Error;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "minigrep",
+    version,
+    about = "Search for PATTERN in each PATH",
+    after_help = "Exit status is 0 if a line is selected, 1 if no lines are selected, and 2 if an error occurred.",
+    override_usage = "minigrep [OPTIONS] PATTERN [PATH]...\n       minigrep [OPTIONS] -e PATTERN... [PATH]..."
+)]
+pub struct Args {
+    /// Use PATTERN for matching (can be repeated)
+    #[arg(
+        short = 'e',
+        long = "regexp",
+        value_name = "PATTERN",
+        allow_hyphen_values = true
+    )]
+    pub patterns: Vec<String>,
+
+    /// Interpret patterns as regular expressions
+    #[arg(long)]
+    pub regex: bool,
+
+    /// Ignore case distinctions (overrides IGNORE_CASE)
+    #[arg(short = 'i', long, overrides_with = "case_sensitive")]
+    pub ignore_case: bool,
+
+    /// Match case exactly (overrides IGNORE_CASE)
+    #[arg(short = 's', long, overrides_with = "ignore_case")]
+    pub case_sensitive: bool,
+
+    /// Unicode case folding used by -i for literal patterns
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = CaseFold::Full)]
+    pub case_fold: CaseFold,
+
+    /// Select non-matching lines
+    #[arg(short = 'v', long)]
+    pub invert_match: bool,
+
+    /// Match only whole words (Unicode word boundaries)
+    #[arg(short = 'w', long)]
+    pub word_regexp: bool,
+
+    /// Match only whole lines
+    #[arg(short = 'x', long)]
+    pub line_regexp: bool,
+
+    /// Let matches span lines: a pattern may contain newlines (\n with --regex) and every line of a match is printed
+    #[arg(
+        short = 'U',
+        long,
+        conflicts_with_all = ["fuzzy", "field", "column", "replace"]
+    )]
+    pub multiline: bool,
+
+    /// Also match substrings within edit distance K of a pattern, printing the distance of each matching line as ~K
+    #[arg(long, value_name = "K", conflicts_with_all = ["regex", "line_regexp"])]
+    pub fuzzy: Option<usize>,
+
+    /// Match only field NAME of each record (one record per line): a CSV header column or a JSON Lines key (/a/b for nested keys)
+    #[arg(long, value_name = "NAME", conflicts_with = "replace")]
+    pub field: Option<String>,
+
+    /// Match only the Nth column of each CSV record (starting at 1)
+    #[arg(long, value_name = "N", conflicts_with_all = ["field", "replace"])]
+    pub column: Option<NonZeroUsize>,
+
+    /// Print results as JSON Lines
+    #[arg(long, conflicts_with_all = ["count", "files_with_matches", "files_without_match"])]
+    pub json: bool,
+
+    /// Print only a count of selected lines per file
+    #[arg(short = 'c', long)]
+    pub count: bool,
+
+    /// Print only names of files with selected lines
+    #[arg(short = 'l', long, overrides_with = "files_without_match")]
+    pub files_with_matches: bool,
+
+    /// Print only names of files without selected lines
+    #[arg(short = 'L', long, overrides_with = "files_with_matches")]
+    pub files_without_match: bool,
+
+    /// Stop reading a file after NUM selected lines (with --in-place, replace only in the first NUM)
+    #[arg(short = 'm', long, value_name = "NUM")]
+    pub max_count: Option<usize>,
+
+    /// Print nothing and exit with status 0 as soon as a line is selected
+    #[arg(short = 'q', long, visible_alias = "silent")]
+    pub quiet: bool,
+
+    /// Replace every match with TEMPLATE in the output ($1, ${name} refer to capture groups with --regex)
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        allow_hyphen_values = true,
+        conflicts_with = "json"
+    )]
+    pub replace: Option<String>,
+
+    /// Write replacements back to the files instead of printing matching lines
+    #[arg(
+        long,
+        requires = "replace",
+        conflicts_with_all = ["count", "files_with_matches", "files_without_match", "quiet"]
+    )]
+    pub in_place: bool,
+
+    /// With --in-place, print a unified diff of the changes without writing files
+    #[arg(long, requires = "in_place")]
+    pub dry_run: bool,
+
+    /// When to use colors [default: auto, only on a terminal and without NO_COLOR]
+    #[arg(long, value_enum, value_name = "WHEN")]
+    pub color: Option<ColorChoice>,
+
+    /// Print line numbers
+    #[arg(short = 'n', long)]
+    pub line_number: bool,
+
+    /// Print the byte offset of each line
+    #[arg(short = 'b', long)]
+    pub byte_offset: bool,
+
+    /// Print NUM lines of trailing context
+    #[arg(short = 'A', long, value_name = "NUM")]
+    pub after_context: Option<usize>,
+
+    /// Print NUM lines of leading context
+    #[arg(short = 'B', long, value_name = "NUM")]
+    pub before_context: Option<usize>,
+
+    /// Print NUM lines of output context
+    #[arg(short = 'C', long, value_name = "NUM")]
+    pub context: Option<usize>,
+
+    /// Skip files and directories matching GLOB when searching directories (can be repeated)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Search inside gzip, zstd and xz compressed inputs (detected by content)
+    #[arg(short = 'z', long, conflicts_with = "in_place")]
+    pub search_zip: bool,
+
+    /// Keep reading PATH as it grows, like tail -F (survives truncation and log rotation)
+    #[arg(
+        short = 'f',
+        long,
+        conflicts_with_all = ["count", "files_with_matches", "files_without_match", "in_place", "multiline"]
+    )]
+    pub follow: bool,
+
+    /// Search only files that the trigram index of each directory or its nearest indexed parent (built by `minigrep index build DIR`) says may match
+    #[arg(long, conflicts_with_all = ["search_zip", "follow"])]
+    pub index: bool,
+
+    /// Number of files to search in parallel [default: number of CPUs]
+    #[arg(long, value_name = "NUM")]
+    pub threads: Option<NonZeroUsize>,
+
+    /// PATTERN (unless -e is given, or @PROFILE from the config file) followed by files or directories to search ("-" or none for stdin)
+    #[arg(value_name = "ARGS")]
+    pub positional: Vec<String>,
+}
+
+/// 첫 번째 위치 인수가 `args`의 몇 번째 항목인지 (없으면 `None`)
+/// 옵션의 값은 `@이름`처럼 보이더라도 위치 인수로 세지 않음
+pub fn first_positional(args: &[String]) -> Option<usize> {
+    let command = Args::command();
+    let takes_value = |arg: Option<&Arg>| arg.is_some_and(|arg| arg.get_action().takes_values());
+
+    let mut index = 1;
+    while let Some(arg) = args.get(index) {
+        index += 1;
+        if arg == "--" {
+            return (index < args.len()).then_some(index);
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            // `--exclude GLOB`처럼 값이 다음 인수이면 함께 건너뜀 (`--exclude=GLOB`은 한 인수)
+            let found = command.get_arguments().find(|a| a.get_long() == Some(long));
+            if takes_value(found) {
+                index += 1;
+            }
+        } else if let Some(shorts) = arg.strip_prefix('-').filter(|shorts| !shorts.is_empty()) {
+            // `-nA3`처럼 묶인 짧은 옵션은 값을 받는 옵션 뒤가 그 값이고, 뒤가 비었으면 다음 인수가 값
+            for (at, short) in shorts.char_indices() {
+                let found = command
+                    .get_arguments()
+                    .find(|a| a.get_short() == Some(short));
+                if takes_value(found) {
+                    if at + short.len_utf8() == shorts.len() {
+                        index += 1;
+                    }
+                    break;
+                }
+            }
+        } else {
+            return Some(index - 1);
+        }
+    }
+    None
+}
+
+/// `minigrep index` 하위 명령의 인수
+#[derive(Parser, Debug)]
+#[command(
+    bin_name = "minigrep index",
+    about = "Manage the trigram indexes used by --index",
+    after_help = "To search for the word \"index\" itself, use `minigrep -e index` or `minigrep -- index`."
+)]
+pub struct IndexArgs {
+    #[command(subcommand)]
+    pub command: IndexCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IndexCommand {
+    /// Build the index of DIR, or update it by re-reading only files whose modification time or size changed
+    Build {
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+    },
+}
+
+/// 명령줄 인수를 `Config`로 만들 때 발생하는 에러
+#[derive(Debug)]
+pub enum ConfigError {
+    /// 잘못된 옵션 (`--help`, `--version` 출력 요청도 여기에 포함됨)
+    Cli(clap::Error),
+    /// 검색어가 없음
+    MissingPattern,
+    /// 설정 파일을 읽지 못함 (경로, 에러 메시지)
+    File(PathBuf, String),
+    /// `-f`에 파일이 하나가 아님
+    FollowPaths,
+    /// `--fuzzy`의 거리가 검색어의 문자 수 이상이라 모든 라인이 빈 일치로 선택됨 (거리, 검색어)
+    FuzzyDistance(usize, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Cli(err) => write!(f, "{err}"),
+            ConfigError::MissingPattern => write!(f, "no pattern given"),
+            ConfigError::File(path, err) => write!(f, "{}: {err}", path.display()),
+            ConfigError::FollowPaths => write!(f, "--follow needs exactly one file"),
+            ConfigError::FuzzyDistance(max, pattern) => write!(
+                f,
+                "--fuzzy {max} would match every line: pattern '{pattern}' has only {} character(s)",
+                pattern.chars().count()
+            ),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<clap::Error> for ConfigError {
+    fn from(err: clap::Error) -> ConfigError {
+        ConfigError::Cli(err)
+    }
+}
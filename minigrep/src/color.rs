@@ -0,0 +1,66 @@
+//! `--color`: 터미널 색상 출력
+//!
+//! 색상은 GNU grep의 기본값을 따름
+//! (파일 이름은 자주색, 라인 번호와 오프셋은 초록색, 구분자는 청록색, 일치 부분은 굵은 빨간색)
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+
+/// 색상 사용 여부
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    // 표준 출력이 터미널이고 `NO_COLOR`가 없을 때만 사용
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// 실제로 색상을 사용할지 결정
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            // https://no-color.org: 비어 있지 않은 `NO_COLOR`가 있으면 색상을 끔
+            ColorChoice::Auto => {
+                io::stdout().is_terminal()
+                    && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && env::var_os("TERM").is_none_or(|term| term != "dumb")
+            }
+        }
+    }
+}
+
+/// 출력 요소별 색상
+#[derive(Clone, Copy)]
+pub enum Style {
+    Path,
+    LineNumber, // 라인 번호, 바이트 오프셋
+    Separator,  // `:`, `-`, `--`
+    Match,
+}
+
+impl Style {
+    // ANSI SGR 매개변수
+    fn code(self) -> &'static str {
+        match self {
+            Style::Path => "35",
+            Style::LineNumber => "32",
+            Style::Separator => "36",
+            Style::Match => "1;31",
+        }
+    }
+}
+
+/// `color`이면 `text`를 `style` 색상으로 감싸서 출력
+pub fn paint(out: &mut impl Write, color: bool, style: Style, text: &[u8]) -> io::Result<()> {
+    if !color || text.is_empty() {
+        return out.write_all(text);
+    }
+    write!(out, "\x1b[{}m", style.code())?;
+    out.write_all(text)?;
+    out.write_all(b"\x1b[0m")
+}
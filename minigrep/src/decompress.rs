@@ -0,0 +1,89 @@
+//! `-z`: 압축된 입력을 풀면서 검색
+//!
+//! 확장자 대신 입력 앞부분의 매직 바이트로 형식을 판단하므로,
+//! 이름이 바뀐 로그나 표준 입력으로 들어온 압축 데이터도 처리할 수 있음
+//! 압축 해제도 스트리밍 방식이라 파일 전체를 메모리에 올리지 않음
+use flate2::bufread::MultiGzDecoder;
+use std::io::{self, BufRead, BufReader};
+use xz2::bufread::XzDecoder;
+
+/// 지원하는 압축 형식
+#[derive(Debug, PartialEq)]
+enum Format {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Format {
+    // 알 수 없는 형식이면 `None` (압축되지 않은 입력으로 취급)
+    fn detect(header: &[u8]) -> Option<Format> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Format::Gzip)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Format::Zstd)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Format::Xz)
+        } else {
+            None
+        }
+    }
+}
+
+/// 압축된 입력이면 압축을 푸는 리더로 감싸고, 그렇지 않으면 그대로 반환
+pub fn decoder<'a>(mut reader: Box<dyn BufRead + 'a>) -> io::Result<Box<dyn BufRead + 'a>> {
+    // 로테이션된 로그처럼 여러 개를 이어 붙인 압축 스트림도 끝까지 읽음
+    Ok(match Format::detect(reader.fill_buf()?) {
+        Some(Format::Gzip) => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Some(Format::Zstd) => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        Some(Format::Xz) => Box::new(BufReader::new(XzDecoder::new_multi_decoder(reader))),
+        None => reader,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Read, Write};
+
+    fn decompress(compressed: Vec<u8>) -> String {
+        let mut contents = String::new();
+        decoder(Box::new(&compressed[..]))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn concatenated_gzip_members() {
+        let mut compressed = Vec::new();
+        for part in ["first\n", "second\n"] {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(part.as_bytes()).unwrap();
+            compressed.extend(encoder.finish().unwrap());
+        }
+
+        assert_eq!("first\nsecond\n", decompress(compressed));
+    }
+
+    #[test]
+    fn zstd_and_xz() {
+        let zstd = zstd::encode_all(&b"zstd line\n"[..], 0).unwrap();
+        assert_eq!("zstd line\n", decompress(zstd));
+
+        let mut xz = Vec::new();
+        xz2::read::XzEncoder::new(&b"xz line\n"[..], 6)
+            .read_to_end(&mut xz)
+            .unwrap();
+        assert_eq!("xz line\n", decompress(xz));
+    }
+
+    #[test]
+    fn plain_input_unchanged() {
+        assert_eq!(None, Format::detect(b"plain text"));
+        assert_eq!("plain\n", decompress(b"plain\n".to_vec()));
+    }
+}
@@ -0,0 +1,232 @@
+//! `--field`, `--column`: CSV와 JSON Lines 레코드의 특정 필드만 검색
+//!
+//! 한 라인을 레코드 하나로 보고, 선택한 필드의 값에만 검색어를 적용한 뒤 레코드 전체를 출력함
+//! 따라서 따옴표 안에 줄바꿈이 있는 CSV 필드는 지원하지 않음 (나뉜 라인은 각각 잘못된 레코드가 됨)
+//! 형식은 파일의 첫 레코드로 판단 (`{`로 시작하면 JSON Lines, 그 밖에는 CSV)
+//! 파싱할 수 없거나 필드가 없는 레코드는 건너뛰고 개수만 셈
+//! 단, CSV 헤더에 없는 열 이름은 모든 레코드가 건너뛰어지므로 파일의 에러로 알림 (`FieldSearch::error`)
+use crate::matcher::{Matcher, PatternMatcher};
+use serde_json::Value;
+use std::io;
+use std::ops::Range;
+
+/// 검색할 필드
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field {
+    // CSV 헤더의 열 이름 또는 JSON 객체의 키 (`/`로 시작하면 JSON Pointer)
+    Name(String),
+    // CSV의 열 위치 (0부터 시작)
+    Column(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Csv,
+    JsonLines,
+}
+
+/// 레코드 하나에서 꺼낸 필드 값
+enum Record {
+    Value(Vec<u8>),
+    Header, // CSV 헤더 (검색하지 않음)
+    Invalid,
+}
+
+/// 파일 하나의 레코드들을 차례로 검사 (헤더 위치 등 파일별 상태를 가짐)
+pub struct FieldSearch<'a> {
+    matcher: &'a PatternMatcher,
+    field: &'a Field,
+    format: Option<Format>,
+    column: Option<usize>, // CSV에서 검색할 열 (이름으로 찾는 경우 헤더를 읽은 뒤 결정)
+    header_read: bool,
+    pub invalid: usize, // 건너뛴 레코드 수
+}
+
+impl<'a> FieldSearch<'a> {
+    pub fn new(matcher: &'a PatternMatcher, field: &'a Field) -> FieldSearch<'a> {
+        let (format, column) = match field {
+            Field::Column(column) => (Some(Format::Csv), Some(*column)),
+            Field::Name(_) => (None, None),
+        };
+
+        FieldSearch {
+            matcher,
+            field,
+            format,
+            column,
+            header_read: false,
+            invalid: 0,
+        }
+    }
+
+    /// 레코드가 선택되는지 판단 (`PatternMatcher::select`와 같은 규칙)
+    /// 필드 값은 따옴표나 이스케이프를 푼 것이라 라인 안의 위치와 다르므로 일치 범위는 비워 둠
+    pub fn select(&mut self, line: &[u8]) -> Option<Vec<Range<usize>>> {
+        if line.trim_ascii().is_empty() || self.missing_column() {
+            return None;
+        }
+
+        let format = *self
+            .format
+            .get_or_insert(if line.trim_ascii_start().starts_with(b"{") {
+                Format::JsonLines
+            } else {
+                Format::Csv
+            });
+        let record = match format {
+            Format::Csv => self.csv_field(line),
+            Format::JsonLines => self.json_field(line),
+        };
+
+        match record {
+            Record::Value(value) => self.matcher.select(&value).map(|_| Vec::new()),
+            Record::Header => None,
+            Record::Invalid => {
+                self.invalid += 1;
+                None
+            }
+        }
+    }
+
+    /// 검색할 수 없는 파일이면 그 이유 (검색을 마친 뒤 확인)
+    pub fn error(&self) -> Option<io::Error> {
+        let Field::Name(name) = self.field else {
+            return None;
+        };
+        self.missing_column().then(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no column named '{name}' in the CSV header"),
+            )
+        })
+    }
+
+    // 열 이름으로 찾는데 헤더에 그 이름이 없음
+    fn missing_column(&self) -> bool {
+        self.header_read && self.column.is_none()
+    }
+
+    fn csv_field(&mut self, line: &[u8]) -> Record {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(line);
+        let mut record = csv::ByteRecord::new();
+        if !matches!(reader.read_byte_record(&mut record), Ok(true)) {
+            return Record::Invalid;
+        }
+
+        // 열 이름으로 찾는 경우 첫 레코드는 헤더
+        if let (Field::Name(name), false) = (self.field, self.header_read) {
+            self.header_read = true;
+            self.column = record.iter().position(|title| title == name.as_bytes());
+            return Record::Header;
+        }
+
+        match self.column.and_then(|column| record.get(column)) {
+            Some(value) => Record::Value(value.to_vec()),
+            None => Record::Invalid,
+        }
+    }
+
+    fn json_field(&self, line: &[u8]) -> Record {
+        let Field::Name(name) = self.field else {
+            return Record::Invalid;
+        };
+        let Ok(object) = serde_json::from_slice::<Value>(line) else {
+            return Record::Invalid;
+        };
+        let value = if name.starts_with('/') {
+            object.pointer(name)
+        } else {
+            object.get(name)
+        };
+
+        // 문자열은 따옴표 없이, 그 밖의 값은 JSON 표현 그대로 검색
+        match value {
+            Some(Value::String(text)) => Record::Value(text.as_bytes().to_vec()),
+            Some(value) => Record::Value(value.to_string().into_bytes()),
+            None => Record::Invalid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::matcher;
+
+    fn selected(args: &[&str], field: Field, lines: &[&str]) -> (Vec<usize>, usize) {
+        let matcher = matcher(args);
+        let mut search = FieldSearch::new(&matcher, &field);
+
+        let selected = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| search.select(line.as_bytes()).is_some())
+            .map(|(index, _)| index)
+            .collect();
+        (selected, search.invalid)
+    }
+
+    #[test]
+    fn csv_columns() {
+        let lines = [
+            "name,city,note",
+            "Kim,Seoul,\"moved to Busan, 2020\"",
+            "Lee,Busan,none",
+            "Park",
+        ];
+
+        // 다른 열에만 있는 `Busan`은 선택되지 않음
+        let by_name = selected(&["Busan"], Field::Name("city".to_string()), &lines);
+        assert_eq!((vec![2], 1), by_name);
+
+        let by_column = selected(&["Busan"], Field::Column(2), &lines);
+        assert_eq!((vec![1], 1), by_column);
+    }
+
+    #[test]
+    fn unknown_column_name() {
+        let matcher = matcher(&["Busan"]);
+        let field = Field::Name("town".to_string());
+        let mut search = FieldSearch::new(&matcher, &field);
+        for line in ["name,city", "Lee,Busan"] {
+            assert!(search.select(line.as_bytes()).is_none());
+        }
+
+        let err = search.error().unwrap();
+        assert_eq!("no column named 'town' in the CSV header", err.to_string());
+        assert_eq!(0, search.invalid);
+
+        // JSON Lines에서는 키가 없는 레코드만 건너뜀
+        let field = Field::Name("town".to_string());
+        let mut search = FieldSearch::new(&matcher, &field);
+        search.select(br#"{"city": "Busan"}"#);
+        assert!(search.error().is_none());
+    }
+
+    #[test]
+    fn json_lines() {
+        let lines = [
+            r#"{"level": "error", "msg": "disk full", "ctx": {"code": 28}}"#,
+            r#"{"level": "info", "msg": "error count reset"}"#,
+            "not json",
+            r#"{"msg": "no level"}"#,
+        ];
+
+        let level = selected(&["error"], Field::Name("level".to_string()), &lines);
+        assert_eq!((vec![0], 2), level);
+
+        let pointer = selected(&["28"], Field::Name("/ctx/code".to_string()), &lines);
+        assert_eq!((vec![0], 3), pointer);
+    }
+
+    #[test]
+    fn inversion_skips_invalid_records() {
+        let lines = [r#"{"level": "error"}"#, r#"{"level": "info"}"#, "{"];
+
+        let inverted = selected(&["-v", "error"], Field::Name("level".to_string()), &lines);
+        assert_eq!((vec![1], 1), inverted);
+    }
+}
@@ -0,0 +1,190 @@
+//! 유니코드 대소문자 접기(case folding)
+//!
+//! `to_lowercase`는 대소문자 비교용이 아니어서 `ß`와 `SS`처럼 길이가 달라지는 경우를 처리하지 못하고,
+//! 변환 후에는 원본 라인의 바이트 위치도 알 수 없게 됨
+//! 여기서는 문자 단위로 접으면서 원본 위치 대응표를 함께 만들어, 일치 범위를 원본 기준으로 되돌림
+use caseless::Caseless;
+use clap::ValueEnum;
+use memchr::memmem;
+use std::iter;
+use std::ops::Range;
+
+/// 대소문자 접기 방식
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum CaseFold {
+    // 한 문자를 한 문자로만 접음 (`ẞ` -> `ß`)
+    Simple,
+    // 한 문자가 여러 문자로 접힐 수 있음 (`ß` -> `ss`, `İ` -> `i̇`)
+    #[default]
+    Full,
+}
+
+impl CaseFold {
+    /// 문자 하나를 접은 결과를 `buf`에 덧붙임
+    fn push(self, c: char, buf: &mut Vec<u8>) {
+        let mut folded = iter::once(c).default_case_fold();
+        let first = folded.next().unwrap_or(c);
+
+        let single = match folded.next() {
+            None => Some(first),
+            // 단순 접기에서는 여러 문자가 되는 경우 대신 소문자 변환 결과가 한 문자일 때만 사용
+            Some(_) if self == CaseFold::Simple => {
+                let mut lower = c.to_lowercase();
+                match (lower.next(), lower.next()) {
+                    (Some(lower), None) => Some(lower),
+                    _ => Some(c),
+                }
+            }
+            Some(_) => None,
+        };
+
+        match single {
+            Some(c) => push_char(c, buf),
+            None => iter::once(c)
+                .default_case_fold()
+                .for_each(|c| push_char(c, buf)),
+        }
+    }
+
+    /// 문자열 전체를 접음 (검색어 준비용)
+    pub fn fold_str(self, s: &str) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(s.len());
+        s.chars().for_each(|c| self.push(c, &mut buf));
+        buf
+    }
+}
+
+fn push_char(c: char, buf: &mut Vec<u8>) {
+    let mut encoded = [0; 4];
+    buf.extend_from_slice(c.encode_utf8(&mut encoded).as_bytes());
+}
+
+/// 접은 라인과 원본 위치 대응표
+pub struct Folded {
+    text: Vec<u8>,
+    // 원본의 문자(또는 잘못된 UTF-8 바이트) 하나마다 `(접은 텍스트 위치, 원본 위치)`
+    // 마지막에는 `(접은 텍스트 길이, 원본 길이)`가 들어 있음
+    boundaries: Vec<(usize, usize)>,
+}
+
+impl Folded {
+    /// 라인 전체를 접음
+    pub fn new(line: &[u8], mode: CaseFold) -> Folded {
+        let mut text = Vec::with_capacity(line.len());
+        let mut boundaries = Vec::with_capacity(line.len() + 1);
+        let mut offset = 0;
+
+        for chunk in line.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                boundaries.push((text.len(), offset));
+                mode.push(c, &mut text);
+                offset += c.len_utf8();
+            }
+            // 잘못된 바이트는 그대로 두며, 올바른 UTF-8인 검색어의 일부와는 일치할 수 없음
+            for &byte in chunk.invalid() {
+                boundaries.push((text.len(), offset));
+                text.push(byte);
+                offset += 1;
+            }
+        }
+        boundaries.push((text.len(), offset));
+
+        Folded { text, boundaries }
+    }
+
+    /// 접은 텍스트의 범위를 원본 범위로 변환
+    /// 범위 양 끝이 원본 문자 경계와 맞지 않으면 (예: `ß`가 접힌 `ss`의 한 글자만 일치) `None`
+    fn original(&self, range: &Range<usize>) -> Option<Range<usize>> {
+        let start = self.boundaries.binary_search_by_key(&range.start, |b| b.0);
+        let end = self.boundaries.binary_search_by_key(&range.end, |b| b.0);
+
+        match (start, end) {
+            (Ok(start), Ok(end)) => Some(self.boundaries[start].1..self.boundaries[end].1),
+            _ => None,
+        }
+    }
+
+    /// 접은 라인 전체가 접은 검색어와 같은지 확인
+    pub fn equals(&self, query: &[u8]) -> bool {
+        self.text == query
+    }
+
+    /// 원본 위치 `start` 이후에서 접은 검색어가 처음으로 원본 문자 경계에 맞게 일치하는 원본 범위
+    /// 같은 라인을 여러 번 검색할 때 다시 접지 않도록 시작 위치를 받음
+    pub fn find_from(&self, query: &[u8], start: usize) -> Option<Range<usize>> {
+        let first = self
+            .boundaries
+            .partition_point(|&(_, original)| original < start);
+        let mut from = self.boundaries.get(first)?.0;
+
+        while let Some(index) = memmem::find(&self.text[from..], query) {
+            let start = from + index;
+            if let Some(range) = self.original(&(start..start + query.len())) {
+                return Some(range);
+            }
+            from = start + 1;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(query: &str, line: &str, mode: CaseFold) -> Option<Range<usize>> {
+        Folded::new(line.as_bytes(), mode).find_from(&mode.fold_str(query), 0)
+    }
+
+    #[test]
+    fn full_folding() {
+        // `ß`(2바이트)가 `ss`로 접혀도 원본 범위는 그대로
+        assert_eq!(Some(4..11), find("STRASSE", "die straße", CaseFold::Full));
+        assert_eq!(Some(0..6), find("ǅemal", "ǆemal", CaseFold::Full));
+        // 한 문자가 접힌 결과의 일부만 일치하는 경우는 제외
+        assert_eq!(None, find("s", "ß", CaseFold::Full));
+    }
+
+    #[test]
+    fn simple_folding() {
+        assert_eq!(None, find("STRASSE", "die straße", CaseFold::Simple));
+        assert_eq!(Some(0..3), find("ß", "ẞ", CaseFold::Simple));
+    }
+
+    #[test]
+    fn dotted_capital_i() {
+        // 터키어 `İ`는 `i` + 결합 점(U+0307)으로 접힘
+        assert_eq!(Some(0..2), find("i\u{307}", "İstanbul", CaseFold::Full));
+        assert_eq!(Some(0..2), find("İ", "İSTANBUL", CaseFold::Simple));
+        assert_eq!(None, find("istanbul", "İstanbul", CaseFold::Full));
+    }
+
+    #[test]
+    fn korean_text_offsets() {
+        // 대소문자가 없는 한글 앞뒤의 라틴 문자도 원본 바이트 위치로 돌아와야 함
+        let line = "// 러스트(Rust) 소유권";
+        let range = find("rUST", line, CaseFold::Full).unwrap();
+
+        assert_eq!("Rust", &line[range]);
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        let folded = Folded::new(b"\xffABC\xc3", CaseFold::Full);
+
+        assert_eq!(Some(1..4), folded.find_from(b"abc", 0));
+    }
+
+    #[test]
+    fn find_from_original_offset() {
+        let line = "Straße und STRASSE";
+        let folded = Folded::new(line.as_bytes(), CaseFold::Full);
+        let query = CaseFold::Full.fold_str("strasse");
+
+        assert_eq!(Some(0..7), folded.find_from(&query, 0));
+        // 원본 문자 중간의 위치는 다음 문자부터
+        assert_eq!(Some(12..19), folded.find_from(&query, 1));
+        assert_eq!(None, folded.find_from(&query, 13));
+    }
+}
@@ -0,0 +1,141 @@
+//! `-f`: `tail -F`처럼 계속 늘어나는 파일을 따라가며 읽기
+//!
+//! 파일 끝에 도달해도 끝났다고 알리지 않고, 내용이 추가될 때까지 기다렸다가 이어서 읽음
+//! 검색기에는 끝나지 않는 입력으로 보이므로 라인 번호와 문맥은 일반 검색과 똑같이 이어짐
+//! - 파일이 잘리면 (`> app.log`) 처음부터 다시 읽음
+//! - 같은 경로에 다른 파일이 생기면 (로그 로테이션) 이전 파일을 끝까지 읽은 뒤 새 파일을 처음부터 읽음
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+// 파일 끝에서 새 내용을 확인하는 간격
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 파일 끝에서 기다리는 리더
+pub struct Follow {
+    path: PathBuf,
+    file: File,
+    id: Option<(u64, u64)>, // 열어 둔 파일의 (장치, inode)
+    position: u64,          // 열어 둔 파일에서 읽은 위치
+    interval: Duration,
+}
+
+impl Follow {
+    pub fn open(path: &Path) -> io::Result<Follow> {
+        let file = File::open(path)?;
+        let id = file_id(&file.metadata()?);
+
+        Ok(Follow {
+            path: path.to_path_buf(),
+            file,
+            id,
+            position: 0,
+            interval: POLL_INTERVAL,
+        })
+    }
+
+    // 파일이 잘렸거나 교체되었으면 처음부터 다시 읽을 준비를 하고 `true`
+    fn reopen(&mut self) -> io::Result<bool> {
+        if self.file.metadata()?.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            return Ok(true);
+        }
+
+        // 로테이션 중이라 경로에 파일이 없으면 새 파일이 생길 때까지 기다림
+        let id = match fs::metadata(&self.path) {
+            Ok(metadata) => file_id(&metadata),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        if id == self.id {
+            return Ok(false);
+        }
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+
+        self.id = file_id(&file.metadata()?);
+        self.file = file;
+        self.position = 0;
+        Ok(true)
+    }
+}
+
+impl Read for Follow {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.file.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                self.position += read as u64;
+                return Ok(read);
+            }
+            if !self.reopen()? {
+                thread::sleep(self.interval);
+            }
+        }
+    }
+}
+
+// 같은 경로의 파일이 바뀌었는지 판단할 식별자
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+// 다른 플랫폼에서는 잘린 경우만 알아챌 수 있음
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::{BufRead, BufReader, Write};
+    use std::sync::mpsc;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn survives_truncation_and_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "one\n").unwrap();
+
+        let mut follow = Follow::open(&path).unwrap();
+        follow.interval = Duration::from_millis(5);
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(follow).lines() {
+                if sender.send(line.unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+        assert_eq!("one", lines.recv_timeout(TIMEOUT).unwrap());
+
+        // 추가된 내용
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"two\nthree\n").unwrap();
+        assert_eq!("two", lines.recv_timeout(TIMEOUT).unwrap());
+        assert_eq!("three", lines.recv_timeout(TIMEOUT).unwrap());
+
+        // 잘린 뒤 다시 쓰인 내용
+        fs::write(&path, "4\n").unwrap();
+        assert_eq!("4", lines.recv_timeout(TIMEOUT).unwrap());
+
+        // 로테이션: 이전 파일에 남은 내용을 읽은 뒤 새 파일로 넘어감
+        file.write_all(b"five\n").unwrap();
+        fs::rename(&path, dir.path().join("app.log.1")).unwrap();
+        fs::write(&path, "six\n").unwrap();
+        assert_eq!("five", lines.recv_timeout(TIMEOUT).unwrap());
+        assert_eq!("six", lines.recv_timeout(TIMEOUT).unwrap());
+    }
+}
@@ -0,0 +1,386 @@
+//! `--fuzzy K`: 편집 거리(Levenshtein)가 K 이하인 부분 문자열 검색
+//!
+//! Myers의 비트 병렬 알고리즘으로 동적 계획법 표의 한 열을 비트 벡터로 나타내어
+//! 텍스트의 문자 하나를 검색어 길이 64자마다 몇 번의 비트 연산으로 처리함
+//! 거리는 바이트가 아닌 문자 단위로 계산하며, 잘못된 UTF-8 바이트는 어떤 문자와도 일치하지 않음
+use std::collections::HashMap;
+use std::iter;
+use std::ops::Range;
+use std::str;
+
+const WORD: usize = u64::BITS as usize;
+
+/// 검색어 하나의 비트 벡터 검색기
+pub struct Fuzzy {
+    forward: Table,
+    backward: Table, // 뒤집은 검색어 (일치 끝에서 거꾸로 시작 위치를 찾을 때 사용)
+    max: usize,      // 허용하는 최대 거리
+    ignore_case: bool,
+}
+
+/// 일치 범위와 그 편집 거리
+#[derive(Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub range: Range<usize>,
+    pub distance: usize,
+}
+
+// 문자별로 검색어에서 그 문자가 나오는 위치의 비트 집합 (64자 단위 블록)
+struct Table {
+    len: usize, // 검색어의 문자 수
+    ascii: Vec<Vec<u64>>,
+    other: HashMap<char, Vec<u64>>,
+    none: Vec<u64>, // 검색어에 없는 문자
+}
+
+// 동적 계획법 표의 한 열 (블록마다 위아래 칸의 차이가 +1, -1인 위치를 비트 벡터로 나타냄)
+struct Column {
+    pv: Vec<u64>,
+    mv: Vec<u64>,
+    score: usize, // 마지막 행의 값 (검색어 전체와의 거리)
+}
+
+impl Fuzzy {
+    pub fn new(pattern: &str, max: usize, ignore_case: bool) -> Fuzzy {
+        let chars: Vec<char> = pattern.chars().map(|c| fold(c, ignore_case)).collect();
+        let reversed: Vec<char> = chars.iter().rev().copied().collect();
+
+        Fuzzy {
+            forward: Table::new(&chars),
+            backward: Table::new(&reversed),
+            max,
+            ignore_case,
+        }
+    }
+
+    /// `start` 이후에서 거리가 `max` 이하인 첫 부분 문자열
+    /// 끝이 가장 앞인 일치를 고른 뒤, 끝을 늘려도 거리가 늘어나지 않는 동안은 계속 늘림
+    /// (`hell`보다 `hello`, `러스`보다 `러스드`를 선택)
+    pub fn find_at(&self, line: &[u8], start: usize) -> Option<FuzzyMatch> {
+        if self.forward.len <= self.max {
+            // 모든 문자를 지워도 되므로 빈 문자열이 바로 일치
+            return Some(FuzzyMatch {
+                range: start..start,
+                distance: self.forward.len,
+            });
+        }
+
+        let mut column = self.forward.column();
+        let mut found: Option<FuzzyMatch> = None;
+        for (_, end, c) in chars(&line[start..]) {
+            self.forward.advance(&mut column, self.fold(c), false);
+            match &mut found {
+                None if column.score <= self.max => {
+                    found = Some(FuzzyMatch {
+                        range: start..start + end,
+                        distance: column.score,
+                    })
+                }
+                Some(best) if column.score <= best.distance => {
+                    best.range.end = start + end;
+                    best.distance = column.score;
+                }
+                Some(_) => break,
+                None => {}
+            }
+        }
+
+        let mut found = found?;
+        // 일치 끝에서 거꾸로 읽으며 같은 거리가 되는 가장 먼 위치를 시작으로 함
+        // 검색어보다 `distance`자 넘게 길어지면 거리가 더 커질 수밖에 없으므로 거기서 멈춤
+        let window: Vec<_> = chars(&line[found.range.clone()]).collect();
+        let mut column = self.backward.column();
+        let mut match_start = found.range.end;
+        for (char_start, _, c) in window
+            .into_iter()
+            .rev()
+            .take(self.backward.len + found.distance)
+        {
+            self.backward.advance(&mut column, self.fold(c), true);
+            if column.score <= found.distance {
+                match_start = found.range.start + char_start;
+            }
+        }
+        found.range.start = match_start;
+        Some(found)
+    }
+
+    /// 텍스트 전체와 검색어 사이의 편집 거리
+    pub fn distance(&self, text: &[u8]) -> usize {
+        if self.forward.len == 0 {
+            return chars(text).count();
+        }
+        let mut column = self.forward.column();
+        for (_, _, c) in chars(text) {
+            self.forward.advance(&mut column, self.fold(c), true);
+        }
+        column.score
+    }
+
+    /// 텍스트 전체가 검색어와 거리 `max` 이내인지 여부
+    pub fn matches(&self, text: &[u8]) -> bool {
+        self.distance(text) <= self.max
+    }
+
+    fn fold(&self, c: Option<char>) -> Option<char> {
+        c.map(|c| fold(c, self.ignore_case))
+    }
+}
+
+impl Table {
+    fn new(chars: &[char]) -> Table {
+        let blocks = chars.len().div_ceil(WORD).max(1);
+        let mut ascii = vec![vec![0; blocks]; 128];
+        let mut other = HashMap::new();
+
+        for (index, &c) in chars.iter().enumerate() {
+            let bits = if c.is_ascii() {
+                &mut ascii[c as usize]
+            } else {
+                other.entry(c).or_insert_with(|| vec![0; blocks])
+            };
+            bits[index / WORD] |= 1 << (index % WORD);
+        }
+
+        Table {
+            len: chars.len(),
+            ascii,
+            other,
+            none: vec![0; blocks],
+        }
+    }
+
+    fn column(&self) -> Column {
+        Column {
+            pv: vec![!0; self.none.len()],
+            mv: vec![0; self.none.len()],
+            score: self.len,
+        }
+    }
+
+    fn peq(&self, c: Option<char>) -> &[u64] {
+        match c {
+            Some(c) if c.is_ascii() => &self.ascii[c as usize],
+            Some(c) => self.other.get(&c).unwrap_or(&self.none),
+            None => &self.none,
+        }
+    }
+
+    // 텍스트 문자 하나만큼 열을 진행 (Hyyrö의 블록 단위 확장)
+    // `anchored`이면 첫 행이 0, 1, 2, ...로 늘어나 일치가 텍스트 시작에 고정되고,
+    // 아니면 첫 행이 모두 0이라 일치가 텍스트의 어디서든 시작할 수 있음
+    fn advance(&self, column: &mut Column, c: Option<char>, anchored: bool) {
+        let peq = self.peq(c);
+        // 블록 사이로 전달되는 가로 방향 차이 (+1, 0, -1)
+        let mut carry: i32 = if anchored { 1 } else { 0 };
+
+        for (block, &eq) in peq.iter().enumerate() {
+            let (pv, mv) = (column.pv[block], column.mv[block]);
+            // 마지막 블록은 검색어의 마지막 문자 위치까지만 사용
+            let last_bit = if block + 1 == peq.len() {
+                (self.len - 1) % WORD
+            } else {
+                WORD - 1
+            };
+
+            let xv = eq | mv;
+            let eq = if carry < 0 { eq | 1 } else { eq };
+            let xh = ((eq & pv).wrapping_add(pv) ^ pv) | eq;
+            let mut ph = mv | !(xh | pv);
+            let mut mh = pv & xh;
+
+            let out = if ph >> last_bit & 1 == 1 {
+                1
+            } else if mh >> last_bit & 1 == 1 {
+                -1
+            } else {
+                0
+            };
+
+            ph <<= 1;
+            mh <<= 1;
+            if carry < 0 {
+                mh |= 1;
+            } else if carry > 0 {
+                ph |= 1;
+            }
+            column.pv[block] = mh | !(xv | ph);
+            column.mv[block] = ph & xv;
+            carry = out;
+        }
+
+        column.score = column.score.saturating_add_signed(carry as isize);
+    }
+}
+
+/// 검색어를 `max + 1`개 조각으로 나눔
+/// 편집 한 번은 조각 하나만 망가뜨리므로, 거리가 `max` 이하인 일치에는 적어도 한 조각이 그대로 들어 있음
+/// 검색어가 `max`자 이하라 빈 조각이 생기면 `None`
+pub fn pieces(pattern: &str, max: usize) -> Option<Vec<&str>> {
+    let bounds: Vec<usize> = pattern
+        .char_indices()
+        .map(|(index, _)| index)
+        .chain([pattern.len()])
+        .collect();
+    let len = bounds.len() - 1;
+    if len <= max {
+        return None;
+    }
+
+    let count = max + 1;
+    let pieces = (0..count)
+        .map(|i| &pattern[bounds[i * len / count]..bounds[(i + 1) * len / count]])
+        .collect();
+    Some(pieces)
+}
+
+// `-i`이면 소문자로 바꿔서 비교 (한 문자로 바뀌는 단순 접기만 사용)
+fn fold(c: char, ignore_case: bool) -> char {
+    if ignore_case {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+// 텍스트의 문자들 (시작 오프셋, 끝 오프셋, 문자)
+// 잘못된 UTF-8 바이트는 한 바이트씩 `None`
+fn chars(text: &[u8]) -> impl Iterator<Item = (usize, usize, Option<char>)> + '_ {
+    let mut offset = 0;
+    iter::from_fn(move || {
+        let rest = &text[offset..];
+        let &first = rest.first()?;
+        // 대부분의 텍스트는 ASCII이므로 한 바이트 문자는 바로 처리
+        let (c, len) = if first.is_ascii() {
+            (Some(first as char), 1)
+        } else {
+            let width = match first {
+                0xc2..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf4 => 4,
+                _ => 0,
+            };
+            match rest.get(..width).map(str::from_utf8) {
+                Some(Ok(decoded)) if width > 0 => (decoded.chars().next(), width),
+                _ => (None, 1),
+            }
+        };
+
+        let start = offset;
+        offset += len;
+        Some((start, offset, c))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(pattern: &str, max: usize, line: &'a str) -> Option<(&'a str, usize)> {
+        let found = Fuzzy::new(pattern, max, false).find_at(line.as_bytes(), 0)?;
+        Some((&line[found.range], found.distance))
+    }
+
+    #[test]
+    fn typos_within_distance() {
+        assert_eq!(
+            Some(("connection", 0)),
+            find("connection", 1, "lost connection")
+        );
+        assert_eq!(
+            Some(("conection", 1)),
+            find("connection", 1, "lost conection")
+        );
+        assert_eq!(
+            Some(("connectoin", 2)),
+            find("connection", 2, "a connectoin")
+        );
+        assert_eq!(None, find("connection", 1, "a connectoin"));
+        // 일치 끝을 늘려서 거리가 줄어들면 더 나은 일치를 선택
+        assert_eq!(Some(("hello", 0)), find("hello", 1, "say hello world"));
+        assert_eq!(Some(("hellp", 1)), find("hello", 1, "hellp hello"));
+    }
+
+    #[test]
+    fn characters_not_bytes() {
+        assert_eq!(Some(("러스드", 1)), find("러스트", 1, "언어 러스드 책"));
+        assert_eq!(
+            Some((1, 1)),
+            Fuzzy::new("ab", 1, false)
+                .find_at(b"\xffa\xffb", 0)
+                .map(|m| (m.range.start, m.distance))
+        );
+    }
+
+    #[test]
+    fn long_patterns_span_blocks() {
+        let pattern = "abcdefghij".repeat(10);
+        let mut line = format!("xx{pattern}yy");
+        line.replace_range(50..51, "?");
+        line.remove(80);
+
+        let fuzzy = Fuzzy::new(&pattern, 2, false);
+        let found = fuzzy.find_at(line.as_bytes(), 0).unwrap();
+        assert_eq!(2, found.distance);
+        assert_eq!(2, fuzzy.distance(&line.as_bytes()[found.range]));
+        assert_eq!(
+            None,
+            Fuzzy::new(&pattern, 1, false).find_at(line.as_bytes(), 0)
+        );
+    }
+
+    // 동적 계획법 표를 그대로 채우는 편집 거리
+    fn naive(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+        for (i, x) in a.chars().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, &y) in b.iter().enumerate() {
+                let next = (diagonal + usize::from(x != y))
+                    .min(row[j] + 1)
+                    .min(row[j + 1] + 1);
+                diagonal = row[j + 1];
+                row[j + 1] = next;
+            }
+        }
+        row[b.len()]
+    }
+
+    #[test]
+    fn distance_matches_table() {
+        let words = [
+            "",
+            "a",
+            "kitten",
+            "sitting",
+            "러스트",
+            "rust",
+            "trust",
+            "abcabcabc",
+        ];
+        for a in words {
+            let long = a.repeat(20);
+            for b in words {
+                assert_eq!(naive(a, b), Fuzzy::new(a, 0, false).distance(b.as_bytes()));
+                assert_eq!(
+                    naive(&long, b),
+                    Fuzzy::new(&long, 0, false).distance(b.as_bytes())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn exact_pieces() {
+        assert_eq!(Some(vec!["conne", "ction"]), pieces("connection", 1));
+        assert_eq!(Some(vec!["러", "스", "트"]), pieces("러스트", 2));
+        assert_eq!(None, pieces("ab", 2));
+    }
+
+    #[test]
+    fn ignore_case() {
+        let fuzzy = Fuzzy::new("Error", 1, true);
+        assert_eq!(1, fuzzy.find_at(b"ERRO: disk", 0).unwrap().distance);
+    }
+}
@@ -0,0 +1,870 @@
+//! `minigrep index build DIR`, `--index`: 트라이그램 색인으로 검색할 파일 줄이기
+//!
+//! 색인은 디렉터리의 파일마다 그 파일에 나오는 모든 3바이트 조각(트라이그램)을 모아,
+//! 트라이그램마다 그것을 가진 파일 번호 목록(포스팅 목록)으로 `DIR/.minigrep-index`에 저장함
+//! 검색할 때는 검색어에 반드시 들어 있어야 하는 트라이그램의 포스팅 목록만 읽어서,
+//! 그 트라이그램을 모두 가진 파일만 후보로 골라 평소처럼 검색함 (색인은 후보를 고르는 데만 사용)
+//! 색인을 만든 뒤 바뀌었거나 새로 생긴 파일은 항상 후보이므로 색인이 오래되어도 결과는 정확함
+//! 다시 만들 때는 수정 시각과 크기가 그대로인 파일은 이전 결과를 재사용함
+//!
+//! 색인 파일의 형식 (정수는 모두 리틀 엔디언)
+//! - 형식 버전 (`u32`), 파일 목록의 길이 (`u64`), 파일 목록 (`bincode`, 경로순이며 순서가 파일 번호)
+//! - 트라이그램 수 (`u64`), 트라이그램 목록 (트라이그램 순, 항목마다 트라이그램 `u32`, 포스팅 위치 `u64`, 개수 `u32`)
+//! - 포스팅 목록 (파일 번호 `u32`, 트라이그램마다 오름차순)
+use crate::fuzzy;
+use crate::walk;
+use crate::Config;
+use regex_syntax::hir::{Hir, HirKind};
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 색인 파일 이름 (색인한 디렉터리 바로 아래에 생성되며, 검색과 색인에서 제외됨)
+pub const FILE_NAME: &str = ".minigrep-index";
+
+// 형식이 바뀌면 올려서 이전 색인을 다시 만들게 함
+const VERSION: u32 = 2;
+
+// 트라이그램 목록의 항목 하나의 크기
+const DIRECTORY_ENTRY_LEN: u64 = 4 + 8 + 4;
+
+// 색인을 만들 때 메모리에 모았다가 정렬해서 임시 파일로 내보내는 (트라이그램, 파일 번호) 쌍의 수 (32MB)
+const BATCH_LEN: usize = 1 << 22;
+
+/// 디렉터리 하나의 색인
+/// 파일 목록만 메모리에 올리고, 포스팅 목록은 필요한 트라이그램의 것만 읽음
+pub struct Index {
+    files: Vec<FileEntry>, // 경로순 (위치가 파일 번호)
+    path: PathBuf,
+    reader: BufReader<File>,
+    position: u64,  // `reader`에서 다음에 읽을 위치
+    directory: u64, // 트라이그램 목록이 시작하는 위치
+    trigrams: u64,  // 트라이그램 목록의 항목 수
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+    path: Vec<u8>, // 디렉터리 기준 상대 경로 (UTF-8이 아닐 수 있어 바이트로 저장)
+    modified: SystemTime,
+    len: u64,
+    binary: bool, // 바이너리 파일은 검색하지 않으므로 트라이그램도 없음
+}
+
+/// `index build`의 결과
+#[derive(Debug, Default, PartialEq)]
+pub struct BuildStats {
+    pub files: usize,   // 색인에 있는 파일 수
+    pub updated: usize, // 새로 읽은 파일 수
+    pub removed: usize, // 사라져서 색인에서 뺀 파일 수
+}
+
+impl Index {
+    /// `dir`의 색인에서 파일 목록을 읽음
+    pub fn load(dir: &Path) -> io::Result<Index> {
+        let path = dir.join(FILE_NAME);
+        let mut reader = BufReader::new(File::open(&path)?);
+        if read_u32(&mut reader)? != VERSION {
+            return Err(io::Error::other(
+                "index was built by another version of minigrep",
+            ));
+        }
+        let len = read_u64(&mut reader)?;
+        let files: Vec<FileEntry> =
+            bincode::deserialize_from((&mut reader).take(len)).map_err(io::Error::other)?;
+        reader.seek(SeekFrom::Start(4 + 8 + len))?;
+        let trigrams = read_u64(&mut reader)?;
+        let directory = 4 + 8 + len + 8;
+
+        Ok(Index {
+            files,
+            path,
+            reader,
+            position: directory,
+            directory,
+            trigrams,
+        })
+    }
+
+    /// `dir`의 색인을 만들거나, 이미 있으면 바뀐 파일만 다시 읽어서 갱신
+    /// 포스팅 목록은 정해진 크기씩 정렬해서 임시 파일로 내보낸 뒤 병합하므로 메모리 사용량은 색인 크기와 무관함
+    pub fn build(dir: &Path) -> io::Result<BuildStats> {
+        let mut old = Index::load(dir).ok();
+        let mut stats = BuildStats::default();
+
+        // 파일마다 (파일 정보, 경로, 재사용할 이전 색인의 파일 번호)
+        let mut files = Vec::new();
+        let mut kept = 0;
+        let root = dir.to_string_lossy();
+        for path in walk::files(&root, &[]) {
+            let path = path.map_err(io::Error::other)?;
+            let Ok(relative) = path.strip_prefix(dir) else {
+                continue;
+            };
+            let key = relative.as_os_str().as_encoded_bytes().to_vec();
+            let metadata = fs::metadata(&path)?;
+            let (modified, len) = (metadata.modified()?, metadata.len());
+
+            let previous = old.as_ref().and_then(|old| {
+                let id = old.find(&key)?;
+                kept += 1;
+                let entry = &old.files[id];
+                (entry.modified == modified && entry.len == len).then_some(id)
+            });
+            let binary = previous.is_some_and(|id| old.as_ref().unwrap().files[id].binary);
+            let entry = FileEntry {
+                path: key,
+                modified,
+                len,
+                binary,
+            };
+            files.push((entry, path, previous));
+        }
+        files.sort_unstable_by(|(a, ..), (b, ..)| a.path.cmp(&b.path));
+        stats.files = files.len();
+        stats.removed = old.as_ref().map_or(0, |old| old.files.len()) - kept;
+
+        let mut postings = Postings::new(BATCH_LEN);
+        // 바뀌지 않은 파일은 이전 색인의 포스팅 목록을 파일 번호만 바꿔서 재사용
+        if let Some(old) = &old {
+            let mut ids = vec![None; old.files.len()];
+            for (id, (.., previous)) in files.iter().enumerate() {
+                if let Some(previous) = previous {
+                    ids[*previous] = Some(id as u32);
+                }
+            }
+            old.for_each_posting(|trigram, found| {
+                for &previous in found {
+                    if let Some(id) = ids[previous as usize] {
+                        postings.push(trigram, id)?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        let mut trigrams = Trigrams::new();
+        for (id, (entry, path, previous)) in files.iter_mut().enumerate() {
+            if previous.is_some() {
+                continue;
+            }
+            stats.updated += 1;
+            match trigrams.read(path)? {
+                Some(found) => {
+                    for trigram in found {
+                        postings.push(trigram, id as u32)?;
+                    }
+                }
+                None => entry.binary = true,
+            }
+        }
+        // 이전 색인을 닫은 뒤 교체
+        drop(old.take());
+
+        // 다른 검색이 쓰다 만 색인을 읽지 않도록 임시 파일에 쓴 뒤 교체
+        let files: Vec<FileEntry> = files.into_iter().map(|(entry, ..)| entry).collect();
+        let temp = tempfile::NamedTempFile::new_in(dir)?;
+        let mut writer = BufWriter::new(temp);
+        write_index(&mut writer, &files, postings)?;
+        let temp = writer.into_inner().map_err(|err| err.into_error())?;
+        temp.persist(dir.join(FILE_NAME)).map_err(|err| err.error)?;
+
+        Ok(stats)
+    }
+
+    // 경로가 `key`인 파일의 번호
+    fn find(&self, key: &[u8]) -> Option<usize> {
+        self.files
+            .binary_search_by(|entry| entry.path[..].cmp(key))
+            .ok()
+    }
+
+    // 트라이그램 순서대로 (트라이그램, 그것을 가진 파일 번호들)을 `f`에 넘김
+    // 포스팅 목록은 트라이그램 목록과 같은 순서로 이어져 있으므로 두 곳을 각각 처음부터 차례로 읽음
+    fn for_each_posting(&self, mut f: impl FnMut(u32, &[u32]) -> io::Result<()>) -> io::Result<()> {
+        let mut directory = BufReader::new(File::open(&self.path)?);
+        directory.seek(SeekFrom::Start(self.directory))?;
+        let mut postings = BufReader::new(File::open(&self.path)?);
+        postings.seek(SeekFrom::Start(
+            self.directory + self.trigrams * DIRECTORY_ENTRY_LEN,
+        ))?;
+
+        let mut found = Vec::new();
+        for _ in 0..self.trigrams {
+            let (trigram, _, count) = read_entry(&mut directory)?;
+            found.clear();
+            for _ in 0..count {
+                found.push(read_u32(&mut postings)?);
+            }
+            f(trigram, &found)?;
+        }
+        Ok(())
+    }
+
+    // `trigram`을 가진 파일 번호들 (오름차순)
+    // 트라이그램 목록은 정렬되어 있으므로 파일에서 바로 이진 탐색
+    fn postings(&mut self, trigram: u32) -> io::Result<Vec<u32>> {
+        let (mut low, mut high) = (0, self.trigrams);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let (found, offset, count) = self.directory_entry(middle)?;
+            match found.cmp(&trigram) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return self.postings_at(offset, count),
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    // 트라이그램 목록의 `position`번째 항목 (트라이그램, 포스팅 위치, 개수)
+    fn directory_entry(&mut self, position: u64) -> io::Result<(u32, u64, u32)> {
+        let mut entry = [0; DIRECTORY_ENTRY_LEN as usize];
+        self.read_at(self.directory + position * DIRECTORY_ENTRY_LEN, &mut entry)?;
+        read_entry(&mut &entry[..])
+    }
+
+    fn postings_at(&mut self, offset: u64, count: u32) -> io::Result<Vec<u32>> {
+        let mut bytes = vec![0; count as usize * 4];
+        self.read_at(offset, &mut bytes)?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|id| u32::from_le_bytes(id.try_into().unwrap()))
+            .collect())
+    }
+
+    // `offset`부터 `buf`를 채움
+    // 이진 탐색의 마지막 단계처럼 가까운 곳을 읽을 때는 다시 읽지 않고 버퍼에 남은 데이터를 사용
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        self.reader
+            .seek_relative(offset as i64 - self.position as i64)?;
+        self.position = offset;
+        self.reader.read_exact(buf)?;
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+
+    // 색인 기준으로 `path` 파일(색인한 디렉터리 기준 경로는 `relative`)에 일치하는 라인이 있을 수 있는지 여부
+    // `selected`는 검색어 조건을 만족하는 파일 번호들
+    fn may_match(&self, path: &Path, relative: &Path, selected: &[u32]) -> bool {
+        let Some(id) = self.find(relative.as_os_str().as_encoded_bytes()) else {
+            return true;
+        };
+        // 색인 이후에 바뀐 파일은 색인을 믿을 수 없음
+        let entry = &self.files[id];
+        let changed = fs::metadata(path)
+            .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+            .is_ok_and(|current| current != (entry.modified, entry.len));
+
+        changed || selected.binary_search(&(id as u32)).is_ok()
+    }
+}
+
+// 정렬된 (트라이그램, 파일 번호) 쌍들
+type Pairs = Box<dyn Iterator<Item = io::Result<(u32, u32)>>>;
+
+// 색인을 만드는 동안 모으는 (트라이그램, 파일 번호) 쌍
+// `capacity`개가 모이면 정렬해서 임시 파일(정렬된 구간)로 내보내고, 다 모은 뒤 구간들을 병합함
+struct Postings {
+    batch: Vec<(u32, u32)>,
+    capacity: usize,
+    runs: Vec<File>,
+}
+
+impl Postings {
+    fn new(capacity: usize) -> Postings {
+        Postings {
+            batch: Vec::new(),
+            capacity,
+            runs: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, trigram: u32, id: u32) -> io::Result<()> {
+        self.batch.push((trigram, id));
+        if self.batch.len() >= self.capacity {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.batch.sort_unstable();
+        let mut writer = BufWriter::new(tempfile::tempfile()?);
+        for &(trigram, id) in &self.batch {
+            writer.write_all(&trigram.to_le_bytes())?;
+            writer.write_all(&id.to_le_bytes())?;
+        }
+        self.runs.push(rewind(writer)?);
+        self.batch.clear();
+        Ok(())
+    }
+
+    // 모든 쌍을 트라이그램, 파일 번호 순으로 병합해서
+    // `directory`에 트라이그램 목록(포스팅 위치는 `postings` 기준), `postings`에 파일 번호들을 쓰고 트라이그램 수를 돌려줌
+    fn merge(mut self, directory: &mut impl Write, postings: &mut impl Write) -> io::Result<u64> {
+        self.batch.sort_unstable();
+        let mut sources: Vec<Pairs> = vec![Box::new(self.batch.into_iter().map(Ok))];
+        for run in self.runs {
+            let mut reader = BufReader::new(run);
+            sources.push(Box::new(iter::from_fn(move || {
+                read_pair(&mut reader).transpose()
+            })));
+        }
+
+        let mut heap = BinaryHeap::new();
+        for (source, pairs) in sources.iter_mut().enumerate() {
+            if let Some(pair) = pairs.next() {
+                heap.push(Reverse((pair?, source)));
+            }
+        }
+        let (mut current, mut offset, mut count, mut trigrams) = (None, 0, 0, 0);
+        while let Some(Reverse(((trigram, id), source))) = heap.pop() {
+            if let Some(pair) = sources[source].next() {
+                heap.push(Reverse((pair?, source)));
+            }
+            if current != Some(trigram) {
+                if let Some(previous) = current {
+                    write_entry(directory, previous, offset, count)?;
+                    offset += u64::from(count) * 4;
+                    trigrams += 1;
+                }
+                (current, count) = (Some(trigram), 0);
+            }
+            postings.write_all(&id.to_le_bytes())?;
+            count += 1;
+        }
+        if let Some(last) = current {
+            write_entry(directory, last, offset, count)?;
+            trigrams += 1;
+        }
+        Ok(trigrams)
+    }
+}
+
+// 파일 목록과, 트라이그램마다 그것을 가진 파일 번호 목록을 씀
+fn write_index(out: &mut impl Write, files: &[FileEntry], postings: Postings) -> io::Result<()> {
+    let header = bincode::serialize(files).map_err(io::Error::other)?;
+    let mut directory = BufWriter::new(tempfile::tempfile()?);
+    let mut ids = BufWriter::new(tempfile::tempfile()?);
+    let trigrams = postings.merge(&mut directory, &mut ids)?;
+
+    out.write_all(&VERSION.to_le_bytes())?;
+    out.write_all(&(header.len() as u64).to_le_bytes())?;
+    out.write_all(&header)?;
+    out.write_all(&trigrams.to_le_bytes())?;
+    // 포스팅 위치를 색인 파일 기준으로 바꿔서 씀
+    let base = 4 + 8 + header.len() as u64 + 8 + trigrams * DIRECTORY_ENTRY_LEN;
+    let mut directory = BufReader::new(rewind(directory)?);
+    for _ in 0..trigrams {
+        let (trigram, offset, count) = read_entry(&mut directory)?;
+        write_entry(out, trigram, base + offset, count)?;
+    }
+    io::copy(&mut BufReader::new(rewind(ids)?), out)?;
+    Ok(())
+}
+
+// 다 쓴 임시 파일을 처음부터 다시 읽을 수 있게 함
+fn rewind(writer: BufWriter<File>) -> io::Result<File> {
+    let mut file = writer.into_inner().map_err(|err| err.into_error())?;
+    file.rewind()?;
+    Ok(file)
+}
+
+fn write_entry(out: &mut impl Write, trigram: u32, offset: u64, count: u32) -> io::Result<()> {
+    out.write_all(&trigram.to_le_bytes())?;
+    out.write_all(&offset.to_le_bytes())?;
+    out.write_all(&count.to_le_bytes())
+}
+
+fn read_entry(reader: &mut impl Read) -> io::Result<(u32, u64, u32)> {
+    Ok((read_u32(reader)?, read_u64(reader)?, read_u32(reader)?))
+}
+
+// 정렬된 구간의 다음 (트라이그램, 파일 번호), 끝이면 `None`
+fn read_pair(reader: &mut impl BufRead) -> io::Result<Option<(u32, u32)>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((read_u32(reader)?, read_u32(reader)?)))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// `--index`: 검색할 디렉터리들의 색인과 검색어 조건
+pub struct Candidates {
+    // (검색할 디렉터리, 색인한 디렉터리 기준의 그 디렉터리 경로, 색인, 검색어 조건을 만족하는 파일 번호들)
+    indexes: Vec<(PathBuf, PathBuf, Index, Vec<u32>)>,
+    all: bool, // 색인으로 거를 수 없는 검색
+}
+
+impl Candidates {
+    /// 검색할 디렉터리마다 그 디렉터리나 가장 가까운 상위 디렉터리의 색인을 읽음 (색인이 없으면 에러)
+    pub fn load(config: &Config) -> io::Result<Candidates> {
+        let query = Query::new(config);
+        let mut indexes = Vec::new();
+        for path in config.paths.iter().map(PathBuf::from) {
+            if !path.is_dir() {
+                continue;
+            }
+            let error = |err: io::Error| {
+                io::Error::new(
+                    err.kind(),
+                    format!(
+                        "{}: cannot read index ({err}); run `minigrep index build {}`",
+                        path.display(),
+                        path.display()
+                    ),
+                )
+            };
+            let (root, prefix) = find_root(&path).map_err(error)?;
+            let mut index = Index::load(&root).map_err(error)?;
+            let selected = query.files(&mut index).map_err(error)?;
+            indexes.push((path, prefix, index, selected.unwrap_or_default()));
+        }
+
+        Ok(Candidates {
+            indexes,
+            all: query == Query::All,
+        })
+    }
+
+    /// 파일을 검색해야 하는지 여부 (색인이 없는 파일은 항상 검색)
+    pub fn contains(&self, path: &Path) -> bool {
+        if self.all {
+            return true;
+        }
+        self.indexes.iter().all(
+            |(dir, prefix, index, selected)| match path.strip_prefix(dir) {
+                Ok(relative) => index.may_match(path, &prefix.join(relative), selected),
+                Err(_) => true,
+            },
+        )
+    }
+}
+
+// 색인이 있는 가장 가까운 디렉터리(`dir` 자신 포함)와, 그 디렉터리 기준의 `dir` 경로
+fn find_root(dir: &Path) -> io::Result<(PathBuf, PathBuf)> {
+    let dir = fs::canonicalize(dir)?;
+    let root = dir
+        .ancestors()
+        .find(|ancestor| ancestor.join(FILE_NAME).is_file())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "no index in this directory or its parents",
+            )
+        })?;
+    let prefix = dir
+        .strip_prefix(root)
+        .unwrap_or(Path::new(""))
+        .to_path_buf();
+    Ok((root.to_path_buf(), prefix))
+}
+
+/// 일치하는 파일이 반드시 가지고 있어야 하는 트라이그램 조건
+#[derive(Debug, PartialEq)]
+enum Query {
+    All,                // 색인으로 거를 수 없음
+    Trigrams(Vec<u32>), // 모두 있어야 함
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+impl Query {
+    fn new(config: &Config) -> Query {
+        // 일치하지 않는 파일도 결과가 되거나 (`-c`의 `:0`, `--json` 요약의 검색한 파일 수),
+        // 파일 내용 그대로가 아닌 값을 검색하는 경우 (대소문자 접기, CSV와 JSON의 이스케이프)
+        if config.invert_match
+            || config.files_without_match
+            || config.count
+            || config.json
+            || config.ignore_case
+            || config.field.is_some()
+        {
+            return Query::All;
+        }
+
+        let patterns = config.patterns.iter();
+        Query::or(if let Some(max) = config.fuzzy {
+            // 근사 일치에도 검색어 조각 중 하나는 그대로 들어 있음
+            patterns
+                .map(|pattern| match fuzzy::pieces(pattern, max) {
+                    Some(pieces) => Query::or(pieces.into_iter().map(Query::literal).collect()),
+                    None => Query::All,
+                })
+                .collect()
+        } else if config.regex {
+            patterns
+                .map(|pattern| match regex_syntax::parse(pattern) {
+                    Ok(hir) => Query::from_hir(&hir),
+                    Err(_) => Query::All,
+                })
+                .collect()
+        } else {
+            patterns.map(Query::literal).collect()
+        })
+    }
+
+    fn literal(text: impl AsRef<[u8]>) -> Query {
+        let text = text.as_ref();
+        if text.len() < 3 {
+            return Query::All;
+        }
+        let mut trigrams: Vec<u32> = text.windows(3).map(trigram).collect();
+        trigrams.sort_unstable();
+        trigrams.dedup();
+        Query::Trigrams(trigrams)
+    }
+
+    // 정규 표현식에서 반드시 나와야 하는 문자열들
+    // 문자 클래스, `(?i)` 등은 조건 없이 통과시킴
+    fn from_hir(hir: &Hir) -> Query {
+        match hir.kind() {
+            HirKind::Literal(literal) => Query::literal(&literal.0),
+            HirKind::Capture(capture) => Query::from_hir(&capture.sub),
+            HirKind::Repetition(repetition) if repetition.min > 0 => {
+                Query::from_hir(&repetition.sub)
+            }
+            HirKind::Alternation(alternatives) => {
+                Query::or(alternatives.iter().map(Query::from_hir).collect())
+            }
+            HirKind::Concat(parts) => {
+                // 이어진 문자열은 합쳐서 경계에 걸친 트라이그램도 조건에 넣음
+                let mut queries = Vec::new();
+                let mut text = Vec::new();
+                for part in parts {
+                    match part.kind() {
+                        HirKind::Literal(literal) => text.extend_from_slice(&literal.0),
+                        _ => {
+                            queries.push(Query::literal(&text));
+                            text.clear();
+                            queries.push(Query::from_hir(part));
+                        }
+                    }
+                }
+                queries.push(Query::literal(&text));
+                Query::and(queries)
+            }
+            _ => Query::All,
+        }
+    }
+
+    // 조건이 없는 항목은 빼고 묶음
+    fn and(queries: Vec<Query>) -> Query {
+        let mut queries: Vec<Query> = queries
+            .into_iter()
+            .filter(|query| *query != Query::All)
+            .collect();
+        match queries.len() {
+            0 => Query::All,
+            1 => queries.remove(0),
+            _ => Query::And(queries),
+        }
+    }
+
+    // 하나라도 조건이 없으면 전체가 조건 없음
+    fn or(mut queries: Vec<Query>) -> Query {
+        if queries.is_empty() || queries.contains(&Query::All) {
+            Query::All
+        } else if queries.len() == 1 {
+            queries.remove(0)
+        } else {
+            Query::Or(queries)
+        }
+    }
+
+    // 조건을 만족하는 파일 번호들 (오름차순, 조건이 없으면 `None`)
+    // 조건에 나오는 트라이그램의 포스팅 목록만 읽음
+    fn files(&self, index: &mut Index) -> io::Result<Option<Vec<u32>>> {
+        Ok(match self {
+            Query::All => None,
+            Query::Trigrams(required) => {
+                let mut files = None;
+                for &trigram in required {
+                    let found = intersect(files, index.postings(trigram)?);
+                    // 더 좁힐 것이 없으면 나머지 포스팅 목록은 읽지 않음
+                    if found.is_empty() {
+                        return Ok(Some(found));
+                    }
+                    files = Some(found);
+                }
+                files
+            }
+            Query::And(queries) => {
+                let mut files = None;
+                for query in queries {
+                    if let Some(found) = query.files(index)? {
+                        files = Some(intersect(files, found));
+                    }
+                }
+                files
+            }
+            Query::Or(queries) => {
+                let mut files = Vec::new();
+                for query in queries {
+                    let Some(found) = query.files(index)? else {
+                        return Ok(None);
+                    };
+                    files.extend(found);
+                }
+                files.sort_unstable();
+                files.dedup();
+                Some(files)
+            }
+        })
+    }
+}
+
+// 정렬된 두 파일 번호 목록의 교집합 (`files`가 `None`이면 아직 조건이 없었으므로 `found` 그대로)
+fn intersect(files: Option<Vec<u32>>, found: Vec<u32>) -> Vec<u32> {
+    match files {
+        Some(files) => files
+            .into_iter()
+            .filter(|id| found.binary_search(id).is_ok())
+            .collect(),
+        None => found,
+    }
+}
+
+fn trigram(bytes: &[u8]) -> u32 {
+    u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2])
+}
+
+/// 파일의 트라이그램 집합을 구하는 도구
+/// 가능한 모든 트라이그램(2^24개)의 비트 집합을 파일마다 재사용해 큰 파일도 빠르게 처리
+struct Trigrams {
+    bits: Vec<u64>,
+    found: Vec<u32>, // 이번 파일에서 처음 나온 트라이그램 (비트 집합을 비울 때도 사용)
+}
+
+impl Trigrams {
+    fn new() -> Trigrams {
+        Trigrams {
+            bits: vec![0; (1 << 24) / 64],
+            found: Vec::new(),
+        }
+    }
+
+    // 바이너리 파일이면 `None`
+    fn read(&mut self, path: &Path) -> io::Result<Option<Vec<u32>>> {
+        let mut reader = BufReader::with_capacity(64 * 1024, File::open(path)?);
+        if walk::is_binary(reader.fill_buf()?) {
+            return Ok(None);
+        }
+
+        // 블록 경계에 걸친 트라이그램도 세도록 마지막 두 바이트를 이어서 사용
+        let mut window: u32 = 0;
+        let mut seen = 0;
+        loop {
+            let block = reader.fill_buf()?;
+            if block.is_empty() {
+                break;
+            }
+            for &byte in block {
+                window = (window << 8 | u32::from(byte)) & 0xff_ffff;
+                seen += 1;
+                if seen >= 3 {
+                    self.insert(window);
+                }
+            }
+            let len = block.len();
+            reader.consume(len);
+        }
+
+        let mut trigrams = std::mem::take(&mut self.found);
+        for &trigram in &trigrams {
+            self.bits[trigram as usize / 64] = 0;
+        }
+        trigrams.sort_unstable();
+        Ok(Some(trigrams))
+    }
+
+    fn insert(&mut self, trigram: u32) {
+        let (word, bit) = (trigram as usize / 64, trigram % 64);
+        if self.bits[word] >> bit & 1 == 0 {
+            self.bits[word] |= 1 << bit;
+            self.found.push(trigram);
+        }
+    }
+}
+
+/// `minigrep index build DIR`의 결과를 출력
+pub fn report(out: &mut impl Write, dir: &Path, stats: &BuildStats) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}: indexed {} file(s), {} updated, {} removed",
+        dir.display(),
+        stats.files,
+        stats.updated,
+        stats.removed
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn config(args: &[&str], dir: &Path) -> Config {
+        let dir = dir.to_string_lossy();
+        test_support::config(&[args, &[&dir]].concat())
+    }
+
+    // 색인으로 고른 후보 파일 이름들
+    fn candidates(args: &[&str], dir: &Path) -> Vec<String> {
+        let config = config(args, dir);
+        let candidates = Candidates::load(&config).unwrap();
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name != FILE_NAME && candidates.contains(&dir.join(name)))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn picks_candidate_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("errors.log"),
+            "disk full\nconnection reset\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("access.log"), "GET /index.html 200\n").unwrap();
+        fs::write(dir.path().join("data.bin"), b"connection\0").unwrap();
+        Index::build(dir.path()).unwrap();
+
+        let dir = dir.path();
+        assert_eq!(vec!["errors.log"], candidates(&["connection"], dir));
+        assert_eq!(
+            vec!["access.log", "errors.log"],
+            candidates(&["--regex", r"disk\s+full|GET /\w+"], dir)
+        );
+        assert_eq!(
+            vec!["errors.log"],
+            candidates(&["--fuzzy", "1", "conection"], dir)
+        );
+        assert!(candidates(&["timeout"], dir).is_empty());
+
+        // 색인으로 거를 수 없는 검색
+        assert_eq!(
+            vec!["access.log", "data.bin", "errors.log"],
+            candidates(&["-i", "timeout"], dir)
+        );
+        // 일치하지 않는 파일도 출력에 나오는 경우
+        for args in [&["-c", "timeout"][..], &["--json", "timeout"]] {
+            assert_eq!(
+                vec!["access.log", "data.bin", "errors.log"],
+                candidates(args, dir)
+            );
+        }
+        assert_eq!(
+            vec!["access.log", "data.bin", "errors.log"],
+            candidates(&["--regex", "[a-z]+"], dir)
+        );
+    }
+
+    #[test]
+    fn updates_changed_files_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let (errors, access) = (dir.path().join("errors.log"), dir.path().join("access.log"));
+        fs::write(&errors, "disk full\n").unwrap();
+        fs::write(&access, "GET /\n").unwrap();
+
+        let stats = Index::build(dir.path()).unwrap();
+        assert_eq!((2, 2, 0), (stats.files, stats.updated, stats.removed));
+
+        // 색인 후에 바뀐 파일은 다시 만들기 전에도 후보
+        fs::write(&errors, "connection reset by peer\n").unwrap();
+        assert_eq!(vec!["errors.log"], candidates(&["connection"], dir.path()));
+
+        fs::remove_file(&access).unwrap();
+        let stats = Index::build(dir.path()).unwrap();
+        assert_eq!((1, 1, 1), (stats.files, stats.updated, stats.removed));
+        assert_eq!(vec!["errors.log"], candidates(&["connection"], dir.path()));
+
+        // 바뀌지 않은 파일은 이전 색인의 포스팅 목록에서 트라이그램을 되살려 재사용
+        let stats = Index::build(dir.path()).unwrap();
+        assert_eq!((1, 0, 0), (stats.files, stats.updated, stats.removed));
+        assert_eq!(vec!["errors.log"], candidates(&["connection"], dir.path()));
+        assert!(candidates(&["disk full"], dir.path()).is_empty());
+    }
+
+    #[test]
+    fn merges_spilled_batches() {
+        // 두 쌍마다 임시 파일로 내보내도 트라이그램, 파일 번호 순으로 병합
+        let mut postings = Postings::new(2);
+        for (trigram, id) in [(7, 0), (3, 0), (7, 2), (3, 1), (9, 1), (7, 1), (3, 2)] {
+            postings.push(trigram, id).unwrap();
+        }
+        assert_eq!(3, postings.runs.len());
+
+        let (mut directory, mut ids) = (Vec::new(), Vec::new());
+        assert_eq!(3, postings.merge(&mut directory, &mut ids).unwrap());
+        let entries: Vec<_> = directory
+            .chunks(DIRECTORY_ENTRY_LEN as usize)
+            .map(|mut entry| read_entry(&mut entry).unwrap())
+            .collect();
+        assert_eq!(vec![(3, 0, 3), (7, 12, 3), (9, 24, 1)], entries);
+        let ids: Vec<_> = ids
+            .chunks(4)
+            .map(|id| u32::from_le_bytes(id.try_into().unwrap()))
+            .collect();
+        assert_eq!(vec![0, 1, 2, 0, 1, 2, 1], ids);
+    }
+
+    #[test]
+    fn uses_parent_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("logs");
+        fs::create_dir(&sub).unwrap();
+        fs::write(
+            sub.join("errors.log"),
+            "connection reset
+",
+        )
+        .unwrap();
+        fs::write(
+            sub.join("access.log"),
+            "GET /
+",
+        )
+        .unwrap();
+        Index::build(dir.path()).unwrap();
+
+        // 하위 디렉터리만 검색해도 상위 디렉터리의 색인을 사용
+        assert_eq!(vec!["errors.log"], candidates(&["connection"], &sub));
+
+        let unindexed = tempfile::tempdir().unwrap();
+        let config = config(&["connection"], unindexed.path());
+        assert!(Candidates::load(&config).is_err());
+    }
+
+    #[test]
+    fn regex_requirements() {
+        let query = |pattern| Query::from_hir(&regex_syntax::parse(pattern).unwrap());
+
+        assert_eq!(Query::literal("abcd"), query("abcd"));
+        assert_eq!(Query::All, query("(?i)abcd"));
+        assert_eq!(Query::All, query("ab|cdef"));
+        assert_eq!(
+            Query::And(vec![Query::literal("foo"), Query::literal("bar")]),
+            query(r"foo\d+bar")
+        );
+    }
+}
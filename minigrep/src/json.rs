@@ -0,0 +1,266 @@
+//! `--json`: 검색 결과를 JSON Lines 형식으로 출력
+//!
+//! 한 줄에 하나의 JSON 객체를 출력하며, 모든 객체는 `{"type": ..., "data": {...}}` 형태
+//! - `begin`: 파일의 첫 결과 직전
+//! - `match`, `context`: 일치 라인과 문맥 라인
+//! - `end`: 파일 검색을 마친 뒤 파일별 통계
+//! - `summary`: 모든 검색을 마친 뒤 전체 통계
+use crate::matcher::PatternMatcher;
+use crate::search::Event;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 검색 통계
+#[derive(Clone, Copy, Default)]
+struct Stats {
+    searches: usize,            // 검색한 입력 수
+    searches_with_match: usize, // 일치 라인이 있었던 입력 수
+    bytes_searched: usize,      // 읽은 바이트 수
+    matched_lines: usize,       // 일치 라인 수
+    matches: usize,             // 일치한 부분의 수 (한 라인에 여러 개일 수 있음)
+}
+
+impl Stats {
+    fn add(&mut self, other: &Stats) {
+        self.searches += other.searches;
+        self.searches_with_match += other.searches_with_match;
+        self.bytes_searched += other.bytes_searched;
+        self.matched_lines += other.matched_lines;
+        self.matches += other.matches;
+    }
+
+    fn to_json(self, elapsed: Duration) -> Value {
+        json!({
+            "elapsed": duration(elapsed),
+            "searches": self.searches,
+            "searches_with_match": self.searches_with_match,
+            "bytes_searched": self.bytes_searched,
+            "matched_lines": self.matched_lines,
+            "matches": self.matches,
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct JsonPrinter {
+    file: Stats, // 현재 파일의 통계
+    begun: bool, // 현재 파일의 `begin`을 이미 출력했는지 여부
+    // 병렬 검색에서 복제된 출력기들이 함께 모으는 전체 통계
+    total: Arc<Mutex<Stats>>,
+}
+
+impl JsonPrinter {
+    pub fn new() -> JsonPrinter {
+        JsonPrinter::default()
+    }
+
+    pub fn begin_file(&mut self) {
+        self.file = Stats {
+            searches: 1,
+            ..Stats::default()
+        };
+        self.begun = false;
+    }
+
+    pub fn print(
+        &mut self,
+        out: &mut impl Write,
+        matcher: &PatternMatcher,
+        path: &Path,
+        event: &Event,
+    ) -> io::Result<()> {
+        let (kind, line_number, byte_offset, line, spans) = match event {
+            Event::Match(m) => ("match", m.line_number, m.byte_offset, m.line, &m.spans[..]),
+            Event::Context(c) => ("context", c.line_number, c.byte_offset, c.line, &[][..]),
+            // 문맥 그룹 구분은 라인 번호로 알 수 있으므로 따로 출력하지 않음
+            Event::Break => return Ok(()),
+        };
+
+        if !self.begun {
+            self.begun = true;
+            write_message(out, "begin", json!({ "path": data(path_bytes(path)) }))?;
+        }
+
+        // `-v`로 선택된 라인에는 일치한 부분이 없음
+        let submatches: Vec<Value> = spans
+            .iter()
+            .map(|span| {
+                let mut submatch = json!({
+                    "match": data(&line[span.clone()]),
+                    "start": span.start,
+                    "end": span.end,
+                });
+                // `--fuzzy`이면 일치 부분마다 편집 거리를 함께 출력
+                if let Some(distance) = matcher.distance(&line[span.clone()]) {
+                    submatch["distance"] = distance.into();
+                }
+                submatch
+            })
+            .collect();
+
+        if kind == "match" {
+            self.file.matched_lines += 1;
+            self.file.matches += submatches.len();
+        }
+
+        write_message(
+            out,
+            kind,
+            json!({
+                "path": data(path_bytes(path)),
+                "lines": data(line),
+                "line_number": line_number,
+                "absolute_offset": byte_offset,
+                "submatches": submatches,
+            }),
+        )
+    }
+
+    pub fn end_file(
+        &mut self,
+        out: &mut impl Write,
+        path: &Path,
+        bytes_searched: usize,
+        elapsed: Duration,
+    ) -> io::Result<()> {
+        self.file.bytes_searched = bytes_searched;
+        if self.file.matched_lines > 0 {
+            self.file.searches_with_match = 1;
+        }
+        self.total.lock().unwrap().add(&self.file);
+
+        if !self.begun {
+            return Ok(());
+        }
+        write_message(
+            out,
+            "end",
+            json!({
+                "path": data(path_bytes(path)),
+                "stats": self.file.to_json(elapsed),
+            }),
+        )
+    }
+
+    pub fn finish(&mut self, out: &mut impl Write, elapsed: Duration) -> io::Result<()> {
+        let total = *self.total.lock().unwrap();
+        write_message(
+            out,
+            "summary",
+            json!({
+                "elapsed_total": duration(elapsed),
+                "stats": total.to_json(elapsed),
+            }),
+        )
+    }
+}
+
+fn write_message(out: &mut impl Write, kind: &str, data: Value) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, &json!({ "type": kind, "data": data }))?;
+    out.write_all(b"\n")
+}
+
+fn path_bytes(path: &Path) -> &[u8] {
+    path.as_os_str().as_encoded_bytes()
+}
+
+// UTF-8 문자열은 `{"text": ...}`, 그렇지 않은 바이트는 `{"bytes": base64}`로 표현
+fn data(bytes: &[u8]) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => json!({ "text": text }),
+        Err(_) => json!({ "bytes": base64(bytes) }),
+    }
+}
+
+fn duration(elapsed: Duration) -> Value {
+    json!({
+        "secs": elapsed.as_secs(),
+        "nanos": elapsed.subsec_nanos(),
+        "human": format!("{:.6}s", elapsed.as_secs_f64()),
+    })
+}
+
+// 표준 base64 인코딩 (패딩 포함)
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Match;
+    use crate::test_support::matcher;
+
+    #[test]
+    fn match_message() {
+        let matcher = matcher(&["--json", "o", "poem.txt"]);
+        let mut printer = JsonPrinter::new();
+        let mut out = Vec::new();
+
+        printer.begin_file();
+        let event = Event::Match(Match {
+            line_number: 3,
+            byte_offset: 10,
+            spans: vec![2..3, 5..6],
+            line: b"two dogs",
+        });
+        printer
+            .print(&mut out, &matcher, Path::new("a.txt"), &event)
+            .unwrap();
+        printer
+            .end_file(&mut out, Path::new("a.txt"), 20, Duration::ZERO)
+            .unwrap();
+
+        let lines: Vec<Value> = out
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+
+        assert_eq!("begin", lines[0]["type"]);
+        assert_eq!("match", lines[1]["type"]);
+        assert_eq!(3, lines[1]["data"]["line_number"]);
+        assert_eq!(10, lines[1]["data"]["absolute_offset"]);
+        assert_eq!(
+            json!([
+                { "match": { "text": "o" }, "start": 2, "end": 3 },
+                { "match": { "text": "o" }, "start": 5, "end": 6 },
+            ]),
+            lines[1]["data"]["submatches"]
+        );
+        assert_eq!("end", lines[2]["type"]);
+        assert_eq!(2, lines[2]["data"]["stats"]["matches"]);
+        assert_eq!(20, lines[2]["data"]["stats"]["bytes_searched"]);
+    }
+
+    #[test]
+    fn binary_data() {
+        assert_eq!(json!({ "text": "러스트" }), data("러스트".as_bytes()));
+        assert_eq!(json!({ "bytes": "/w==" }), data(b"\xff"));
+        assert_eq!("TWFu", base64(b"Man"));
+        assert_eq!("TWE=", base64(b"Ma"));
+    }
+}
@@ -1,50 +1,489 @@
+use clap::Parser;
 use std::error::Error;
-use std::{env, fs};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Instant;
+
+mod cli;
+mod color;
+mod decompress;
+mod field;
+mod fold;
+mod follow;
+mod fuzzy;
+mod index;
+mod json;
+mod matcher;
+mod parallel;
+mod printer;
+mod replace;
+mod search;
+mod searcher;
+mod settings;
+#[cfg(test)]
+mod test_support;
+mod walk;
+
+pub use cli::ConfigError;
+pub use color::ColorChoice;
+pub use field::Field;
+pub use fold::CaseFold;
+pub use matcher::{Matcher, PatternMatcher};
+pub use search::{find_matches, search_reader, Context, Event, Match};
+pub use searcher::{Searcher, Sink, Summary};
+pub use settings::{Profile, Settings};
+
+use field::FieldSearch;
+use follow::Follow;
+use index::{Candidates, Index};
+use json::JsonPrinter;
+use printer::{Printer, StandardPrinter};
+use replace::Replacer;
+use walk::Input;
 
 pub struct Config {
-    pub query: String,
-    pub file_path: String,
+    pub patterns: Vec<String>, // 검색어 (`-e`로 여러 개 지정 가능, 하나라도 일치하면 선택)
+    pub paths: Vec<String>,    // 검색할 파일 또는 디렉터리 (`-`는 표준 입력)
     pub ignore_case: bool,
+    pub case_fold: CaseFold, // `--case-fold`: `-i`에서 사용할 대소문자 접기 방식
+    pub regex: bool,         // `--regex`: 검색어를 정규 표현식으로 해석
+    pub invert_match: bool,  // `-v`: 일치하지 않는 라인을 선택
+    pub word_regexp: bool,   // `-w`: 단어 전체가 일치하는 경우만 선택
+    pub line_regexp: bool,   // `-x`: 라인 전체가 일치하는 경우만 선택
+    pub multiline: bool,     // `-U`: 일치가 여러 라인에 걸칠 수 있음
+    pub field: Option<Field>, // `--field`, `--column`: 레코드의 한 필드에만 검색어를 적용
+    pub fuzzy: Option<usize>, // `--fuzzy`: 편집 거리가 이 값 이하인 부분 문자열도 일치로 인정
+    pub count: bool,         // `-c`: 파일별로 선택된 라인 수만 출력
+    pub files_with_matches: bool, // `-l`: 선택된 라인이 있는 파일 이름만 출력
+    pub files_without_match: bool, // `-L`: 선택된 라인이 없는 파일 이름만 출력
+    pub max_count: Option<usize>, // `-m N`: 파일마다 N개의 라인을 선택하면 검색 중단
+    pub quiet: bool,         // `-q`: 아무것도 출력하지 않고 종료 상태로만 결과를 알림
+    pub json: bool,          // `--json`: 결과를 JSON Lines 형식으로 출력
+    pub replace: Option<String>, // `--replace`: 일치한 부분을 바꿀 템플릿
+    pub in_place: bool,      // `--in-place`: 바꾼 내용을 파일에 다시 씀
+    pub dry_run: bool,       // `--dry-run`: 파일 대신 바뀔 내용을 diff로 출력
+    pub color: ColorChoice,  // `--color`: 색상 사용 여부
+    pub line_number: bool,   // `-n`: 라인 번호 출력
+    pub byte_offset: bool,   // `-b`: 라인 시작의 바이트 오프셋 출력
+    pub before_context: usize, // `-B N`: 일치 라인 앞의 문맥 라인 수
+    pub after_context: usize, // `-A N`: 일치 라인 뒤의 문맥 라인 수
+    pub exclude: Vec<String>, // `--exclude`: 디렉터리 탐색에서 제외할 glob
+    pub search_zip: bool,    // `-z`: gzip, zstd, xz로 압축된 입력의 압축을 풀어서 검색
+    pub follow: bool,        // `-f`: 파일 끝에서 멈추지 않고 추가되는 내용을 계속 검색
+    pub index: bool,         // `--index`: 트라이그램 색인으로 고른 후보 파일만 검색
+    pub threads: usize,      // `--threads N`: 여러 파일을 동시에 검색할 스레드 수
 }
 
 impl Config {
     // new()라는 네이밍은 실패하지 않을 것으로 예상하기 때문에 실패할 가능성이 있는 build가 적절
-    pub fn build(args: &[String]) -> Result<Config, &'static str> {
-        if args.len() < 3 {
-            return Err("not enough arguments");
+    pub fn build(args: &[String]) -> Result<Config, ConfigError> {
+        Config::build_with(args, &Settings::load()?)
+    }
+
+    /// 설정 파일과 환경변수 대신 주어진 `settings`를 기본값으로 사용
+    pub fn build_with(args: &[String], settings: &Settings) -> Result<Config, ConfigError> {
+        let mut parsed = cli::Args::try_parse_from(args)?;
+
+        // 검색어 자리에 `@이름`이 오면 저장된 프로필로 펼친 뒤 다시 파싱
+        if parsed.patterns.is_empty() {
+            let expanded =
+                cli::first_positional(args).and_then(|index| settings.expand(args, index));
+            if let Some(expanded) = expanded {
+                parsed = cli::Args::try_parse_from(expanded)?;
+            }
+        }
+        let args = parsed;
+
+        // `-e`가 없으면 첫 번째 위치 인수가 검색어
+        // `--` 뒤의 인수는 `-`로 시작하더라도 위치 인수로 취급됨
+        let mut positional = args.positional.into_iter();
+        let patterns = if args.patterns.is_empty() {
+            vec![positional.next().ok_or(ConfigError::MissingPattern)?]
+        } else {
+            args.patterns
+        };
+
+        // 경로가 없으면 표준 입력을 검색
+        let mut paths: Vec<String> = positional.collect();
+        if paths.is_empty() {
+            paths.push("-".to_string());
+        }
+        // 따라가며 읽는 입력은 끝나지 않으므로 파일 하나만 가능
+        if args.follow && (paths.len() != 1 || paths[0] == "-" || Path::new(&paths[0]).is_dir()) {
+            return Err(ConfigError::FollowPaths);
+        }
+
+        // 검색어의 모든 문자를 지워도 되는 거리이면 어디서나 빈 일치가 생김
+        if let Some(max) = args.fuzzy {
+            if let Some(pattern) = patterns
+                .iter()
+                .find(|pattern| pattern.chars().count() <= max)
+            {
+                return Err(ConfigError::FuzzyDistance(max, pattern.clone()));
+            }
         }
 
-        // String 타입의 clone()은 참조자를 젖아하는 것보다 시간과 메모리를 더 소모하지만,
-        // 라이프타임 관리를 할 필요가 없어 코드를 매우 직관적으로 만듦
-        let query = args[1].clone();
-        let file_path = args[2].clone();
+        // 명시적인 `-i`/`-s` 옵션이 없을 때만 설정 값 사용 (환경변수는 `Settings::load`에서 반영됨)
+        let ignore_case = if args.ignore_case || args.case_sensitive {
+            args.ignore_case
+        } else {
+            settings.ignore_case.unwrap_or(false)
+        };
+
+        // 설정 파일의 제외 목록 뒤에 명령줄의 목록을 덧붙임
+        let mut exclude = settings.exclude.clone();
+        exclude.extend(args.exclude);
 
-        // 환경변수에서 값 가져오기
-        // 실행 명령어: IGNORE_CASE=1 cargo run -- to poem.txt
-        let ignore_case = env::var("IGNORE_CASE").is_ok();
+        // `-A`/`-B`가 `-C`보다 우선
+        let context = args.context.unwrap_or(0);
 
         Ok(Config {
-            query,
-            file_path,
+            patterns,
+            paths,
             ignore_case,
+            case_fold: args.case_fold,
+            regex: args.regex,
+            invert_match: args.invert_match,
+            word_regexp: args.word_regexp,
+            line_regexp: args.line_regexp,
+            multiline: args.multiline,
+            fuzzy: args.fuzzy,
+            field: args
+                .field
+                .map(Field::Name)
+                .or(args.column.map(|column| Field::Column(column.get() - 1))),
+            count: args.count,
+            files_with_matches: args.files_with_matches,
+            files_without_match: args.files_without_match,
+            max_count: args.max_count,
+            quiet: args.quiet,
+            json: args.json,
+            replace: args.replace,
+            in_place: args.in_place,
+            dry_run: args.dry_run,
+            color: args.color.or(settings.color).unwrap_or_default(),
+            line_number: args.line_number,
+            byte_offset: args.byte_offset,
+            before_context: args.before_context.unwrap_or(context),
+            after_context: args.after_context.unwrap_or(context),
+            exclude,
+            search_zip: args.search_zip,
+            follow: args.follow,
+            index: args.index,
+            // 지정하지 않으면 사용 가능한 CPU 수만큼 사용
+            threads: args
+                .threads
+                .or_else(|| thread::available_parallelism().ok())
+                .map_or(1, |threads| threads.get()),
         })
     }
 }
 
-pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read_to_string(config.file_path)?;
+/// 모든 입력을 검색한 뒤 선택된 라인이 있었는지 여부를 반환
+pub fn run(config: Config) -> Result<bool, Box<dyn Error>> {
+    let started = Instant::now();
 
-    let results = if config.ignore_case {
-        search_case_insensitive(&config.query, &contents)
+    // 패턴은 한 번만 컴파일한 뒤 모든 파일, 모든 라인에 재사용
+    // 잘못된 패턴은 `regex::Error`로 반환되어 `?`로 전파
+    let matcher = PatternMatcher::new(&config)?;
+
+    // 여러 파일을 검색하는 경우에만 결과 앞에 `경로:`를 붙임
+    let with_filename = config.paths.len() > 1 || walk::has_directory(&config.paths);
+    let summary_only = config.count || config.files_with_matches || config.files_without_match;
+    let replacer = config
+        .replace
+        .as_deref()
+        .map(|template| Replacer::new(template, config.regex));
+    let mut printer = if config.json {
+        Printer::Json(JsonPrinter::new())
+    } else {
+        let mut printer = StandardPrinter::new(
+            with_filename,
+            config.line_number,
+            config.byte_offset,
+            config.before_context + config.after_context > 0 && !summary_only,
+        );
+        printer.replace = replacer.clone();
+        printer.color = config.color.enabled();
+        printer.distance = config.fuzzy.is_some() && !config.invert_match;
+        Printer::Standard(printer)
+    };
+    // `--index`: 색인이 없는 디렉터리가 있으면 검색하지 않고 에러
+    let candidates = if config.index {
+        Some(Candidates::load(&config)?)
     } else {
-        search(&config.query, &contents)
+        None
+    };
+    // 병렬 검색에 `config`를 넘긴 뒤에도 필요한 값
+    let quiet = config.quiet;
+    // 표준 출력은 라인 단위로 버퍼링되므로 찾은 결과가 바로 출력됨
+    let mut out = io::stdout().lock();
+
+    // 여러 파일을 검색할 때만 병렬로 처리하고, 파일 하나는 결과를 바로바로 출력
+    // `-q`는 첫 번째 일치 라인에서 바로 끝내야 하므로 차례로 검색
+    let outcome = match replacer {
+        // 파일을 고쳐 쓸 때는 하나씩 차례로 처리
+        Some(replacer) if config.in_place => for_each_input(
+            walk::inputs(&config.paths, &config.exclude),
+            false,
+            |input| match input {
+                Input::File(path) => {
+                    replacer.edit(&matcher, path, config.max_count, config.dry_run, &mut out)
+                }
+                Input::Stdin => Err(InputError::Read(io::Error::other(
+                    "standard input cannot be edited in place",
+                ))),
+            },
+        ),
+        _ if config.threads > 1 && with_filename && !quiet => {
+            parallel::search_parallel(config, matcher, candidates, &mut printer, &mut out)
+        }
+        _ => for_each_input(inputs(&config, candidates.as_ref()), quiet, |input| {
+            search_input(&config, &matcher, &mut printer, &mut out, input)
+        }),
+    };
+    let outcome = match outcome.and_then(|outcome| {
+        if !quiet {
+            printer.finish(&mut out, started.elapsed())?;
+        }
+        Ok(outcome)
+    }) {
+        Ok(outcome) => outcome,
+        // 출력을 받는 쪽이 먼저 닫혔으면 (예: `| head`) 조용히 종료
+        // 출력할 결과가 있었으므로 선택된 라인이 있었던 것으로 봄
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(true),
+        Err(err) => return Err(err.into()),
     };
 
-    for line in results {
-        println!("{line}");
+    out.flush()?;
+
+    // `-q`는 선택된 라인을 찾았다면 다른 입력의 에러와 상관없이 성공
+    if outcome.failures > 0 && !(quiet && outcome.matched) {
+        return Err(format!("{} path(s) could not be searched", outcome.failures).into());
+    }
+
+    Ok(outcome.matched)
+}
+
+/// 모든 입력을 처리한 결과
+#[derive(Default)]
+struct Outcome {
+    matched: bool,   // 선택된 라인이 있었던 입력이 있는지 여부
+    failures: usize, // 처리하지 못한 입력 수
+}
+
+// 입력들을 차례로 `process`로 처리
+// `process`는 입력에서 선택된 라인이 있었는지 여부를 반환하며,
+// `stop_at_match`이면 선택된 라인이 있는 첫 입력에서 멈춤
+// 출력 에러가 발생하면 바로 중단
+fn for_each_input(
+    inputs: impl Iterator<Item = Result<Input, ignore::Error>>,
+    stop_at_match: bool,
+    mut process: impl FnMut(&Input) -> Result<bool, InputError>,
+) -> io::Result<Outcome> {
+    let mut outcome = Outcome::default();
+
+    for input in inputs {
+        // 일부 경로에서 에러가 나더라도 나머지 파일은 계속 검색
+        let input = match input {
+            Ok(input) => input,
+            Err(err) => {
+                eprintln!("minigrep: {err}");
+                outcome.failures += 1;
+                continue;
+            }
+        };
+
+        match process(&input) {
+            Ok(matched) => {
+                outcome.matched |= matched;
+                if matched && stop_at_match {
+                    break;
+                }
+            }
+            Err(InputError::Read(err)) => {
+                eprintln!("minigrep: {}: {err}", input.path().display());
+                outcome.failures += 1;
+            }
+            Err(InputError::Write(err)) => return Err(err),
+        }
+    }
+
+    Ok(outcome)
+}
+
+// 검색할 입력 목록
+// `--index`이면 색인상 일치하는 라인이 있을 수 없는 파일은 건너뜀
+fn inputs<'a>(
+    config: &'a Config,
+    candidates: Option<&'a Candidates>,
+) -> impl Iterator<Item = Result<Input, ignore::Error>> + 'a {
+    walk::inputs(&config.paths, &config.exclude).filter(move |input| match (input, candidates) {
+        (Ok(Input::File(path)), Some(candidates)) => candidates.contains(path),
+        _ => true,
+    })
+}
+
+/// `minigrep index build DIR`: 디렉터리의 트라이그램 색인을 만들거나 갱신
+/// `args`는 `index`부터 시작하는 인수
+pub fn run_index(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let cli::IndexCommand::Build { dir } = cli::IndexArgs::parse_from(args).command;
+    if !dir.is_dir() {
+        return Err(format!("{}: not a directory", dir.display()).into());
     }
 
-    // 반환값이 없으며 에러 처리만 필요함을 말함
+    let stats = Index::build(&dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    index::report(&mut io::stdout().lock(), &dir, &stats)?;
+    Ok(())
+}
+
+/// 입력 하나를 검색하는 중에 발생한 에러
+enum InputError {
+    Read(io::Error),  // 입력을 읽지 못함 (다음 입력으로 계속 진행)
+    Write(io::Error), // 결과를 출력하지 못함 (검색 중단)
+}
+
+// 입력을 열어 버퍼를 가진 리더로 만듦
+// `-f`이면 파일 끝에서 기다리며, `-z`이면 압축된 입력은 압축을 풀면서 읽음
+fn open(input: &Input, config: &Config) -> io::Result<Box<dyn BufRead>> {
+    let reader: Box<dyn BufRead> = match input {
+        Input::Stdin => Box::new(io::stdin().lock()),
+        Input::File(path) if config.follow => Box::new(BufReader::new(Follow::open(path)?)),
+        Input::File(path) => Box::new(BufReader::new(File::open(path)?)),
+    };
+    if config.search_zip {
+        decompress::decoder(reader)
+    } else {
+        Ok(reader)
+    }
+}
+
+// 입력 하나를 스트리밍 방식으로 검색하며 결과를 바로 출력
+// 선택된 라인이 있었는지 여부를 반환 (`-L`은 파일 이름을 출력했는지 여부)
+// 병렬 검색에서는 `out`이 작업마다의 메모리 버퍼
+fn search_input(
+    config: &Config,
+    matcher: &PatternMatcher,
+    printer: &mut Printer,
+    out: &mut impl Write,
+    input: &Input,
+) -> Result<bool, InputError> {
+    let mut reader = open(input, config).map_err(InputError::Read)?;
+    let path = input.path();
+
+    // 앞부분만 미리 읽어 바이너리 입력은 건너뜀
+    if walk::is_binary(reader.fill_buf().map_err(InputError::Read)?) {
+        return Ok(false);
+    }
+
+    let mut fields = config
+        .field
+        .as_ref()
+        .map(|field| FieldSearch::new(matcher, field));
+
+    // `-q`, `-l`, `-L`, `-c`는 라인 대신 파일별 요약만 필요
+    let names_only = config.files_with_matches || config.files_without_match;
+    if config.quiet || names_only || config.count {
+        let mut count = 0;
+        search_lines(config, matcher, fields.as_mut(), reader, 0, 0, |event| {
+            if let Event::Match(_) = event {
+                count += 1;
+            }
+            // 라인 수를 세는 경우가 아니면 첫 번째 일치 라인을 찾은 뒤 더 읽을 필요가 없음
+            Ok(config.count && !config.quiet)
+        })
+        .map_err(InputError::Read)?;
+        check_fields(fields.as_ref(), path)?;
+
+        let selected = count > 0;
+        if config.quiet {
+            return Ok(selected);
+        }
+        if config.count {
+            printer
+                .print_count(out, path, count)
+                .map_err(InputError::Write)?;
+            return Ok(selected);
+        }
+        // `-l`은 선택된 라인이 있는 파일, `-L`은 없는 파일의 이름을 출력
+        let listed = selected == config.files_with_matches;
+        if listed {
+            printer.print_path(out, path).map_err(InputError::Write)?;
+        }
+        return Ok(listed);
+    }
+
+    // 출력 에러는 따로 보관해 두었다가 읽기 에러와 구분해서 반환
+    let started = Instant::now();
+    printer.begin_file();
+    let mut written = Ok(());
+    let mut matched = false;
+    let searched = search_lines(
+        config,
+        matcher,
+        fields.as_mut(),
+        reader,
+        config.before_context,
+        config.after_context,
+        |event| {
+            matched |= matches!(event, Event::Match(_));
+            written = printer.print(out, matcher, path, &event);
+            Ok(written.is_ok())
+        },
+    );
+    written.map_err(InputError::Write)?;
+    check_fields(fields.as_ref(), path)?;
+    let bytes_searched = searched.map_err(InputError::Read)?;
+
+    printer
+        .end_file(out, path, bytes_searched, started.elapsed())
+        .map_err(InputError::Write)?;
+    Ok(matched)
+}
+
+// `--field`, `--column`이면 라인 대신 레코드의 필드를 검사하고, `-U`이면 입력 전체에서 검색
+fn search_lines(
+    config: &Config,
+    matcher: &PatternMatcher,
+    fields: Option<&mut FieldSearch>,
+    reader: impl Read,
+    before: usize,
+    after: usize,
+    sink: impl FnMut(Event<'_>) -> io::Result<bool>,
+) -> io::Result<usize> {
+    match fields {
+        Some(fields) => search::search_reader_by(
+            reader,
+            before,
+            after,
+            config.max_count,
+            |line| fields.select(line),
+            sink,
+        ),
+        None if config.multiline => {
+            search::search_multiline(matcher, reader, before, after, config.max_count, sink)
+        }
+        None => search_reader(matcher, reader, before, after, config.max_count, sink),
+    }
+}
+
+// 필드를 찾을 수 없는 파일은 에러, 건너뛴 레코드가 있으면 검색은 계속하되 개수를 알림
+fn check_fields(fields: Option<&FieldSearch>, path: &Path) -> Result<(), InputError> {
+    let Some(fields) = fields else {
+        return Ok(());
+    };
+    if let Some(err) = fields.error() {
+        return Err(InputError::Read(err));
+    }
+    if fields.invalid > 0 {
+        eprintln!(
+            "minigrep: {}: skipped {} invalid record(s)",
+            path.display(),
+            fields.invalid
+        );
+    }
     Ok(())
 }
 
@@ -76,6 +515,7 @@ pub fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<&'a st
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::try_config;
 
     #[test]
     fn case_sensitive() {
@@ -103,4 +543,189 @@ Trust me.";
             search_case_insensitive(query, contents)
         );
     }
+
+    #[test]
+    fn regex_pattern() {
+        // 정규 표현식은 `PatternMatcher`로 검색 (문자 클래스, 앵커, 대안(|), 반복, 캡처 그룹 등)
+        let matcher = PatternMatcher::regex(r"^(Pick|Duct) [a-z]+\.$").unwrap();
+        let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Duct tape.
+Pick 3.";
+
+        assert_eq!(
+            vec!["Pick three.", "Duct tape."],
+            contents
+                .lines()
+                .filter(|line| matcher.find(line.as_bytes()).is_some())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn regex_option() {
+        let config = try_config(&["--regex", "fr[oa]g", "poem.txt"]).unwrap();
+
+        assert!(config.regex);
+        assert_eq!(vec!["fr[oa]g"], config.patterns);
+        assert_eq!(vec!["poem.txt"], config.paths);
+    }
+
+    #[test]
+    fn context_options() {
+        let config = try_config(&["-n", "-C", "2", "-A1", "frog", "poem.txt"]).unwrap();
+
+        assert!(config.line_number);
+        assert!(!config.byte_offset);
+        assert_eq!(2, config.before_context);
+        assert_eq!(1, config.after_context);
+    }
+
+    #[test]
+    fn multiple_paths() {
+        let config = try_config(&["frog", "poem.txt", "src"]).unwrap();
+
+        assert_eq!(vec!["poem.txt", "src"], config.paths);
+    }
+
+    #[test]
+    fn stdin_by_default() {
+        let config = try_config(&["frog"]).unwrap();
+
+        assert_eq!(vec!["-"], config.paths);
+    }
+
+    #[test]
+    fn repeated_patterns() {
+        let config = try_config(&["-e", "frog", "-e", "-bog", "--", "-poem.txt"]).unwrap();
+
+        assert_eq!(vec!["frog", "-bog"], config.patterns);
+        assert_eq!(vec!["-poem.txt"], config.paths);
+    }
+
+    #[test]
+    fn flag_overrides_environment() {
+        let config = try_config(&["-s", "-i", "frog", "poem.txt"]).unwrap();
+        assert!(config.ignore_case);
+
+        let config = try_config(&["-i", "-s", "frog", "poem.txt"]).unwrap();
+        assert!(!config.ignore_case);
+    }
+
+    #[test]
+    fn selection_modes() {
+        let config = try_config(&["-l", "-L", "-m", "2", "-q", "frog"]).unwrap();
+
+        // `-l`과 `-L`은 나중에 준 옵션이 우선
+        assert!(!config.files_with_matches);
+        assert!(config.files_without_match);
+        assert_eq!(Some(2), config.max_count);
+        assert!(config.quiet);
+    }
+
+    #[test]
+    fn settings_precedence() {
+        let settings = Settings::parse(
+            r#"
+            color = "always"
+            ignore-case = true
+            exclude = ["*.log"]
+            "#,
+        )
+        .unwrap();
+
+        let config = Config::build_with(
+            &args(&["minigrep", "--exclude", "*.tmp", "frog"]),
+            &settings,
+        )
+        .unwrap();
+        assert!(config.ignore_case);
+        assert_eq!(ColorChoice::Always, config.color);
+        assert_eq!(vec!["*.log", "*.tmp"], config.exclude);
+
+        // 명령줄 옵션이 설정 파일보다 우선
+        let config = Config::build_with(
+            &args(&["minigrep", "-s", "--color", "never", "frog"]),
+            &settings,
+        )
+        .unwrap();
+        assert!(!config.ignore_case);
+        assert_eq!(ColorChoice::Never, config.color);
+    }
+
+    #[test]
+    fn profile_arguments() {
+        let settings = Settings::parse(
+            r#"
+            [profiles.errors]
+            patterns = ["ERROR", "FATAL"]
+            args = ["-w", "-i"]
+            "#,
+        )
+        .unwrap();
+
+        let config =
+            Config::build_with(&args(&["minigrep", "@errors", "-s", "app.log"]), &settings)
+                .unwrap();
+        assert_eq!(vec!["ERROR", "FATAL"], config.patterns);
+        assert_eq!(vec!["app.log"], config.paths);
+        assert!(config.word_regexp);
+        assert!(!config.ignore_case);
+
+        // 옵션의 값은 프로필 이름과 같더라도 그대로 둠
+        let config = Config::build_with(
+            &args(&[
+                "minigrep",
+                "--exclude",
+                "@errors",
+                "-A",
+                "1",
+                "@errors",
+                "app.log",
+            ]),
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(vec!["ERROR", "FATAL"], config.patterns);
+        assert_eq!(vec!["@errors"], config.exclude);
+        assert_eq!(vec!["app.log"], config.paths);
+
+        // 프로필이 없으면 그대로 검색어
+        let config =
+            Config::build_with(&args(&["minigrep", "@user", "app.log"]), &settings).unwrap();
+        assert_eq!(vec!["@user"], config.patterns);
+    }
+
+    #[test]
+    fn missing_arguments() {
+        assert!(matches!(try_config(&[]), Err(ConfigError::MissingPattern)));
+        assert!(matches!(
+            try_config(&["--bogus", "frog", "poem.txt"]),
+            Err(ConfigError::Cli(_))
+        ));
+        // `-f`는 파일 하나만 따라갈 수 있음
+        for paths in [&[][..], &["a.log", "b.log"], &["-"], &["src"]] {
+            let mut argv = vec!["-f", "frog"];
+            argv.extend(paths);
+            assert!(matches!(try_config(&argv), Err(ConfigError::FollowPaths)));
+        }
+        // 거리가 검색어 길이 이상이면 모든 라인이 일치
+        assert!(matches!(
+            try_config(&["--fuzzy", "3", "-e", "rust", "-e", "ab"]),
+            Err(ConfigError::FuzzyDistance(3, pattern)) if pattern == "ab"
+        ));
+        assert!(try_config(&["--fuzzy", "2", "러스트"]).is_ok());
+        // `--replace`는 줄 전체를 바꾸므로 필드만 검색하는 옵션과 함께 쓸 수 없음
+        for field in [&["--field", "name"][..], &["--column", "2"]] {
+            let mut argv = field.to_vec();
+            argv.extend(["--replace", "toad", "frog", "a.csv"]);
+            assert!(matches!(try_config(&argv), Err(ConfigError::Cli(_))));
+        }
+    }
 }
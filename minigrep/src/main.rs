@@ -1,20 +1,37 @@
 use std::env;
 use std::process;
 
-use minigrep::Config;
+use minigrep::{Config, ConfigError};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     // dbg!(args);
 
+    // `minigrep index ...`는 검색 대신 색인 관리
+    if args.get(1).is_some_and(|arg| arg == "index") {
+        if let Err(e) = minigrep::run_index(&args[1..]) {
+            eprintln!("Application error: {e}");
+            process::exit(2);
+        }
+        return;
+    }
+
     let config = Config::build(&args).unwrap_or_else(|err| {
+        // `clap` 에러는 `--help`, `--version` 출력을 포함해 `clap`이 직접 출력 후 종료
+        if let ConfigError::Cli(err) = err {
+            err.exit();
+        }
         eprintln!("Problem parsing arguments: {err}");
-        process::exit(1);
+        process::exit(2);
     });
 
-    // unwrap_or_else를 사용할 필요가 없는 것은 정상 케이스의 반환값이 없기 때문
-    if let Err(e) = minigrep::run(config) {
-        eprintln!("Application error: {e}");
-        process::exit(1);
+    // grep과 같이 선택된 라인이 있으면 0, 없으면 1, 에러가 발생하면 2로 종료
+    match minigrep::run(config) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Application error: {e}");
+            process::exit(2);
+        }
     }
 }
@@ -0,0 +1,514 @@
+//! 검색어 일치 여부와 일치 위치 판단
+use crate::fold::{CaseFold, Folded};
+use crate::fuzzy::{self, Fuzzy};
+use crate::Config;
+use aho_corasick::{AhoCorasick, Input, MatchKind};
+use memchr::memmem;
+use regex::bytes::{Captures, Regex, RegexBuilder};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// 한 번만 만들어 모든 라인에 재사용하는 검색기
+pub struct PatternMatcher {
+    kind: Kind,
+    word: bool,   // `-w`: 일치 범위 양 끝이 유니코드 단어 경계인 경우만 인정
+    invert: bool, // `-v`: 일치하지 않는 라인을 선택
+}
+
+enum Kind {
+    // 대소문자를 구분하는 단순 문자열 검색 (검색어 전처리 결과를 미리 만들어 둠)
+    Literal(Box<memmem::Finder<'static>>),
+    // 대소문자를 구분하는 여러 문자열 검색
+    Literals(AhoCorasick),
+    // 대소문자를 무시하는 문자열 검색 (검색어는 미리 접어 둠)
+    Folded {
+        queries: Vec<Vec<u8>>,
+        mode: CaseFold,
+        whole_line: bool, // `-x`
+    },
+    Regex(Regex), // 정규 표현식 검색 (그 밖의 모든 경우)
+    // `--fuzzy`: 편집 거리 이내의 근사 검색
+    Fuzzy {
+        patterns: Vec<Fuzzy>,
+        // 일치라면 반드시 그대로 들어 있는 검색어 조각들 (버퍼에서 후보 라인을 찾는 데 사용)
+        pieces: Option<AhoCorasick>,
+    },
+}
+
+impl Kind {
+    fn literal(pattern: &str) -> Kind {
+        Kind::Literal(Box::new(memmem::Finder::new(pattern).into_owned()))
+    }
+
+    fn fuzzy<P: AsRef<str>>(patterns: &[P], max: usize, ignore_case: bool) -> Kind {
+        // 대소문자를 무시하면 조각이 그대로 나타나지 않을 수 있음
+        let pieces = patterns
+            .iter()
+            .map(|pattern| fuzzy::pieces(pattern.as_ref(), max))
+            .collect::<Option<Vec<_>>>()
+            .filter(|_| !ignore_case)
+            .map(|pieces| {
+                AhoCorasick::new(pieces.concat()).expect("literal pieces are always valid")
+            });
+        Kind::Fuzzy {
+            patterns: patterns
+                .iter()
+                .map(|pattern| Fuzzy::new(pattern.as_ref(), max, ignore_case))
+                .collect(),
+            pieces,
+        }
+    }
+
+    fn find_at(&self, haystack: &mut Haystack, start: usize) -> Option<Range<usize>> {
+        let line = haystack.line;
+        match self {
+            Kind::Literal(finder) => finder
+                .find(&line[start..])
+                .map(|index| start + index..start + index + finder.needle().len()),
+            Kind::Literals(automaton) => automaton
+                .find(Input::new(line).span(start..line.len()))
+                .map(|m| m.range()),
+            Kind::Folded {
+                queries,
+                mode,
+                whole_line,
+            } => {
+                let folded = haystack
+                    .folded
+                    .get_or_insert_with(|| Folded::new(line, *mode));
+                if *whole_line {
+                    let matched = start == 0 && queries.iter().any(|query| folded.equals(query));
+                    return matched.then_some(0..line.len());
+                }
+                // 여러 검색어 중 가장 앞에서 일치하는 것을 선택
+                queries
+                    .iter()
+                    .filter_map(|query| folded.find_from(query, start))
+                    .min_by_key(|range| range.start)
+            }
+            Kind::Regex(pattern) => pattern.find_at(line, start).map(|m| m.range()),
+            // 가장 앞에서 시작하는 일치를, 같으면 거리가 가까운 것을 선택
+            Kind::Fuzzy { patterns, .. } => patterns
+                .iter()
+                .filter_map(|fuzzy| fuzzy.find_at(line, start))
+                .min_by_key(|m| (m.range.start, m.distance))
+                .map(|m| m.range),
+        }
+    }
+}
+
+// 같은 라인(`-U`에서는 입력 전체)에서 여러 번 검색할 때 한 번만 계산해 두는 것들
+// 매번 다시 계산하면 `-U`에서 일치 수에 입력 크기를 곱한 만큼 걸림
+struct Haystack<'h> {
+    line: &'h [u8],
+    folded: Option<Folded>,         // 대소문자 무시 문자열 검색의 접은 라인
+    boundaries: Option<Vec<usize>>, // `-w`의 단어 경계
+}
+
+impl<'h> Haystack<'h> {
+    fn new(line: &'h [u8]) -> Haystack<'h> {
+        Haystack {
+            line,
+            folded: None,
+            boundaries: None,
+        }
+    }
+}
+
+impl Kind {
+    // 근사 검색의 일치는 거리가 가장 가까운 범위라 단어 중간에서 끝날 수 있음
+    // 양 끝을 둘러싼 단어 경계까지 넓혀도 거리 이내이면 그 범위 (`rust`로 `trust` 찾기)
+    fn widen_to_words(
+        &self,
+        line: &[u8],
+        boundaries: &[usize],
+        range: &Range<usize>,
+    ) -> Option<Range<usize>> {
+        let Kind::Fuzzy { patterns, .. } = self else {
+            return None;
+        };
+        // 경계 목록은 0과 라인 길이를 포함하므로 두 위치 모두 항상 있음
+        let start = boundaries[boundaries.partition_point(|&b| b <= range.start) - 1];
+        let end = boundaries[boundaries.partition_point(|&b| b < range.end)];
+        let widened = start..end;
+        patterns
+            .iter()
+            .any(|fuzzy| fuzzy.matches(&line[widened.clone()]))
+            .then_some(widened)
+    }
+}
+
+/// 라인(또는 `-U`에서는 입력 전체)에서 일치 위치를 찾는 방법
+///
+/// `find_at`만 구현하면 `Searcher`로 검색할 수 있으며,
+/// 문자열, 정규 표현식, 근사 검색은 `PatternMatcher`가 구현함
+pub trait Matcher {
+    /// `start` 이후에서 처음 일치하는 부분의 바이트 범위를 반환
+    /// `start` 앞의 내용도 앵커와 단어 경계 판단에 사용할 수 있음
+    fn find_at(&self, line: &[u8], start: usize) -> Option<Range<usize>>;
+
+    /// 라인에서 처음 일치하는 부분의 바이트 범위를 반환 (라인이 올바른 UTF-8이 아닐 수도 있음)
+    fn find(&self, line: &[u8]) -> Option<Range<usize>> {
+        self.find_at(line, 0)
+    }
+
+    /// 라인에서 겹치지 않는 모든 일치 범위를 반환
+    fn find_all(&self, line: &[u8]) -> Vec<Range<usize>> {
+        let mut results = Vec::new();
+        let mut start = 0;
+
+        while start <= line.len() {
+            let Some(range) = self.find_at(line, start) else {
+                break;
+            };
+            // 빈 일치는 무한 반복에 빠지지 않도록 한 바이트 건너뜀
+            start = if range.is_empty() {
+                range.end + 1
+            } else {
+                range.end
+            };
+            results.push(range);
+        }
+
+        results
+    }
+
+    /// `-v`처럼 일치하지 않는 라인을 선택하는지 여부
+    fn is_inverted(&self) -> bool {
+        false
+    }
+
+    /// 라인이 선택되는지 판단해 일치한 부분들의 범위를 반환
+    /// `-v`이면 일치하지 않는 라인이 빈 목록으로 선택됨
+    fn select(&self, line: &[u8]) -> Option<Vec<Range<usize>>> {
+        if self.is_inverted() {
+            return self.find(line).is_none().then(Vec::new);
+        }
+        let spans = self.find_all(line);
+        (!spans.is_empty()).then_some(spans)
+    }
+
+    /// 여러 라인이 담긴 버퍼에서 바로 후보 위치를 찾을 수 있는지 여부
+    /// `true`이면 `Searcher`가 후보가 없는 라인들을 `select` 없이 건너뜀
+    fn scans_buffer(&self) -> bool {
+        false
+    }
+
+    /// 버퍼에서 일치 후보가 처음 나타나는 위치
+    /// 후보를 포함한 라인이 실제로 선택되는지는 `select`로 다시 확인함
+    /// 버퍼 검색을 지원하지 않으면 항상 버퍼 시작을 후보로 반환
+    fn find_candidate(&self, haystack: &[u8]) -> Option<usize> {
+        let _ = haystack;
+        Some(0)
+    }
+}
+
+impl PatternMatcher {
+    pub fn new(config: &Config) -> Result<PatternMatcher, regex::Error> {
+        let literal = !config.regex && !config.ignore_case && !config.line_regexp;
+
+        let kind = if let Some(max) = config.fuzzy {
+            Kind::fuzzy(&config.patterns, max, config.ignore_case)
+        } else if literal && config.patterns.len() == 1 {
+            Kind::literal(&config.patterns[0])
+        } else if literal {
+            // 정규 표현식의 `|`와 같이 같은 위치에서는 앞쪽 검색어를 우선함
+            let automaton = AhoCorasick::builder()
+                .match_kind(MatchKind::LeftmostFirst)
+                .build(&config.patterns)
+                .expect("literal patterns are always valid");
+            Kind::Literals(automaton)
+        } else if !config.regex && config.ignore_case && !(config.multiline && config.line_regexp) {
+            // 정규 표현식의 대소문자 무시는 단순 접기만 지원하므로 문자열 검색은 직접 접어서 비교
+            Kind::Folded {
+                queries: config
+                    .patterns
+                    .iter()
+                    .map(|pattern| config.case_fold.fold_str(pattern))
+                    .collect(),
+                mode: config.case_fold,
+                whole_line: config.line_regexp,
+            }
+        } else {
+            // 여러 검색어, 라인 전체 일치는 하나의 정규 표현식으로 합쳐서 처리해야
+            // 원본 라인 기준의 일치 위치를 그대로 얻을 수 있음
+            let alternatives: Vec<String> = config
+                .patterns
+                .iter()
+                .map(|pattern| {
+                    if config.regex {
+                        format!("(?:{pattern})")
+                    } else {
+                        regex::escape(pattern)
+                    }
+                })
+                .collect();
+            let mut pattern = alternatives.join("|");
+            if config.line_regexp {
+                pattern = format!("^(?:{pattern})$");
+            }
+
+            // `-U`는 입력 전체가 한 번에 주어지므로 `^`, `$`가 각 라인의 시작과 끝에서 일치해야 함
+            let pattern = RegexBuilder::new(&pattern)
+                .case_insensitive(config.ignore_case)
+                .multi_line(config.multiline)
+                .build()?;
+            Kind::Regex(pattern)
+        };
+
+        Ok(PatternMatcher {
+            kind,
+            word: config.word_regexp,
+            invert: config.invert_match,
+        })
+    }
+
+    /// 대소문자를 구분하는 문자열 검색
+    pub fn literal(pattern: &str) -> PatternMatcher {
+        PatternMatcher::with_kind(Kind::literal(pattern))
+    }
+
+    /// 정규 표현식 검색
+    pub fn regex(pattern: &str) -> Result<PatternMatcher, regex::Error> {
+        Ok(PatternMatcher::with_kind(Kind::Regex(Regex::new(pattern)?)))
+    }
+
+    /// 편집 거리가 `max` 이하인 부분 문자열 검색
+    /// `max`가 검색어의 문자 수 이상이면 모든 위치에서 빈 일치가 됨 (명령줄에서는 `Config`가 거부)
+    pub fn fuzzy(pattern: &str, max: usize) -> PatternMatcher {
+        PatternMatcher::with_kind(Kind::fuzzy(&[pattern], max, false))
+    }
+
+    fn with_kind(kind: Kind) -> PatternMatcher {
+        PatternMatcher {
+            kind,
+            word: false,
+            invert: false,
+        }
+    }
+
+    /// `start` 이후에서 처음 일치하는 부분의 캡처 그룹 (정규 표현식 검색이 아니면 `None`)
+    pub fn captures_at<'h>(&self, line: &'h [u8], start: usize) -> Option<Captures<'h>> {
+        match &self.kind {
+            Kind::Regex(pattern) => pattern.captures_at(line, start),
+            _ => None,
+        }
+    }
+
+    /// `--fuzzy`이면 일치한 부분과 가장 가까운 검색어 사이의 편집 거리
+    pub fn distance(&self, matched: &[u8]) -> Option<usize> {
+        match &self.kind {
+            Kind::Fuzzy { patterns, .. } => {
+                patterns.iter().map(|fuzzy| fuzzy.distance(matched)).min()
+            }
+            _ => None,
+        }
+    }
+
+    // `Matcher::find_at`과 같지만 접은 라인과 단어 경계를 `haystack`에 두고 재사용
+    fn find_in(&self, haystack: &mut Haystack, from: usize) -> Option<Range<usize>> {
+        let mut start = from;
+        while let Some(range) = self.kind.find_at(haystack, start) {
+            if !self.word {
+                return Some(range);
+            }
+            let line = haystack.line;
+            let boundaries = haystack
+                .boundaries
+                .get_or_insert_with(|| word_boundaries(line));
+            if is_word_bounded(boundaries, &range) {
+                return Some(range);
+            }
+            if let Some(widened) = self.kind.widen_to_words(line, boundaries, &range) {
+                if widened.start >= from {
+                    return Some(widened);
+                }
+            }
+            // 단어 경계가 맞지 않으면 다음 바이트부터 다시 검색
+            if range.start >= line.len() {
+                return None;
+            }
+            start = range.start + 1;
+        }
+
+        None
+    }
+}
+
+impl Matcher for PatternMatcher {
+    // 잘못된 UTF-8 바이트는 어떤 문자와도 일치하지 않음
+    fn find_at(&self, line: &[u8], start: usize) -> Option<Range<usize>> {
+        self.find_in(&mut Haystack::new(line), start)
+    }
+
+    // 기본 구현과 같지만 접은 라인과 단어 경계는 라인마다 한 번만 계산
+    fn find_all(&self, line: &[u8]) -> Vec<Range<usize>> {
+        let mut haystack = Haystack::new(line);
+        let mut results = Vec::new();
+        let mut start = 0;
+
+        while start <= line.len() {
+            let Some(range) = self.find_in(&mut haystack, start) else {
+                break;
+            };
+            start = if range.is_empty() {
+                range.end + 1
+            } else {
+                range.end
+            };
+            results.push(range);
+        }
+
+        results
+    }
+
+    fn is_inverted(&self) -> bool {
+        self.invert
+    }
+
+    // 대소문자를 구분하는 문자열 검색과 조각으로 후보를 찾는 근사 검색만 가능하며,
+    // `-v`는 모든 라인을 확인해야 하므로 제외
+    fn scans_buffer(&self) -> bool {
+        !self.invert
+            && matches!(
+                self.kind,
+                Kind::Literal(_)
+                    | Kind::Literals(_)
+                    | Kind::Fuzzy {
+                        pieces: Some(_),
+                        ..
+                    }
+            )
+    }
+
+    // 후보를 포함한 라인도 `-w` 등으로 선택되지 않을 수 있음
+    fn find_candidate(&self, haystack: &[u8]) -> Option<usize> {
+        match &self.kind {
+            Kind::Literal(finder) => finder.find(haystack),
+            Kind::Literals(automaton) => automaton.find(haystack).map(|m| m.start()),
+            Kind::Fuzzy {
+                pieces: Some(automaton),
+                ..
+            } => automaton.find(haystack).map(|m| m.start()),
+            _ => Some(0),
+        }
+    }
+}
+
+// 유니코드 단어 경계(UAX #29) 위치 목록
+// 잘못된 UTF-8 바이트의 앞뒤도 경계로 취급
+fn word_boundaries(line: &[u8]) -> Vec<usize> {
+    let mut boundaries = vec![0];
+    let mut offset = 0;
+
+    for chunk in line.utf8_chunks() {
+        for (index, segment) in chunk.valid().split_word_bound_indices() {
+            boundaries.push(offset + index + segment.len());
+        }
+        offset += chunk.valid().len();
+
+        for _ in chunk.invalid() {
+            offset += 1;
+            boundaries.push(offset);
+        }
+    }
+
+    boundaries
+}
+
+// 일치 범위 양 끝이 모두 단어 경계여야 함
+fn is_word_bounded(boundaries: &[usize], range: &Range<usize>) -> bool {
+    boundaries.binary_search(&range.start).is_ok() && boundaries.binary_search(&range.end).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::matcher;
+
+    #[test]
+    fn span_of_case_insensitive_literal() {
+        let matcher = matcher(&["-i", "rUsT"]);
+
+        assert_eq!(Some(1..5), matcher.find(b"Trust me."));
+        assert_eq!(None, matcher.find(b"safe, fast, productive."));
+    }
+
+    #[test]
+    fn any_of_patterns() {
+        let matcher = matcher(&["-e", "fast", "-e", "Pick"]);
+
+        assert_eq!(Some(6..10), matcher.find(b"safe, fast, productive."));
+        assert_eq!(Some(0..4), matcher.find(b"Pick three."));
+        assert_eq!(None, matcher.find(b"Duct tape."));
+    }
+
+    #[test]
+    fn whole_words() {
+        let matcher = matcher(&["-w", "rust"]);
+
+        assert_eq!(Some(10..14), matcher.find(b"trust the rust"));
+        assert_eq!(None, matcher.find(b"trust rusty"));
+        assert_eq!(Some(10..14), matcher.find("러스트 rust 언어".as_bytes()));
+        assert_eq!(None, matcher.find("러스트 rust는".as_bytes()));
+    }
+
+    #[test]
+    fn full_case_folding() {
+        let matcher = matcher(&["-i", "STRASSE"]);
+        let line = "큰 길은 Straße입니다".as_bytes();
+
+        assert_eq!("Straße".as_bytes(), &line[matcher.find(line).unwrap()]);
+    }
+
+    #[test]
+    fn unicode_word_boundaries() {
+        // 한글 음절도 단어 문자이므로 조사가 붙으면 다른 단어
+        let korean = matcher(&["-w", "-i", "러스트"]);
+
+        assert_eq!(Some(3..12), korean.find("// 러스트 소유권".as_bytes()));
+        assert_eq!(Some(3..12), korean.find("// 러스트, 소유권".as_bytes()));
+        assert_eq!(None, korean.find("// 러스트는 안전".as_bytes()));
+
+        let accented = matcher(&["-w", "café"]);
+        assert_eq!(None, accented.find("cafés".as_bytes()));
+        assert_eq!(Some(1..6), accented.find("(café)".as_bytes()));
+    }
+
+    #[test]
+    fn every_match_in_line() {
+        let matcher = matcher(&["-e", "o", "-e", "ne"]);
+
+        assert_eq!(vec![0..1, 1..3, 6..7], matcher.find_all(b"one two"));
+        assert!(matcher.find_all(b"three").is_empty());
+    }
+
+    #[test]
+    fn every_match_in_buffer() {
+        // `-U`에서는 입력 전체가 한 라인처럼 주어짐
+        let matcher = matcher(&["-U", "-w", "-i", "strasse"]);
+        let buffer = "Straße\nstrasser STRASSE\nstraße".as_bytes();
+
+        assert_eq!(vec![0..7, 17..24, 25..32], matcher.find_all(buffer));
+    }
+
+    #[test]
+    fn fuzzy_with_words() {
+        let matcher = matcher(&["--fuzzy", "1", "-w", "-e", "rust", "-e", "cargo"]);
+        let line = b"rustacean trust the rusty carg";
+
+        // `trust`, `rusty`의 `rust`는 단어 경계가 맞지 않지만 단어 전체도 거리 1 이내
+        // `rustacean`은 단어 전체로는 거리가 너무 멂
+        assert_eq!(vec![10..15, 20..25, 26..30], matcher.find_all(line));
+        assert_eq!(Some(1), matcher.distance(b"carg"));
+        assert_eq!(Some(2), matcher.distance(b"rsut"));
+    }
+
+    #[test]
+    fn whole_lines_and_inversion() {
+        let matcher = matcher(&["-x", "-v", "Pick three."]);
+
+        assert_eq!(None, matcher.select(b"Pick three."));
+        assert_eq!(Some(Vec::new()), matcher.select(b"Pick three. Or four."));
+    }
+}
@@ -0,0 +1,229 @@
+//! 여러 파일 병렬 검색
+//!
+//! 작업 스레드들이 채널로 받은 파일을 하나씩 검색하고, 출력할 내용을 조각 단위로 메인 스레드에 보냄
+//! 메인 스레드는 지금 순서인 파일의 조각은 바로 표준 출력에 쓰고, 그 뒤의 파일들만 순서가 올 때까지 모아 둠
+use crate::index::Candidates;
+use crate::printer::Printer;
+use crate::walk::Input;
+use crate::{inputs, search_input, Config, InputError, Outcome, PatternMatcher};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::{mem, thread};
+
+// 작업 스레드가 출력을 모아서 보내는 단위
+const CHUNK_LEN: usize = 8 * 1024;
+
+/// 작업 스레드가 메인 스레드로 보내는 메시지 (모두 입력 순서와 함께)
+enum Message {
+    Chunk(usize, Vec<u8>),   // 표준 출력에 쓸 내용의 일부
+    Finished(usize, Finish), // 파일 하나의 검색을 마침
+}
+
+/// 파일 하나의 검색 결과
+struct Finish {
+    matched: bool,         // 선택된 라인이 있었는지 여부
+    error: Option<String>, // 표준 에러에 쓸 메시지
+}
+
+/// 아직 순서가 오지 않은 파일의 결과
+#[derive(Default)]
+struct Pending {
+    buf: Vec<u8>,           // 지금까지 받은 출력
+    finish: Option<Finish>, // 검색을 마쳤으면 그 결과
+}
+
+/// 입력 순서대로 출력
+struct Reorder {
+    pending: BTreeMap<usize, Pending>, // 입력 순서 -> 모아 둔 결과
+    next: usize,                       // 지금 출력 중인 입력 순서
+    started: bool,                     // `next` 파일의 출력을 시작했는지 여부
+    outcome: Outcome,                  // 지금까지 출력한 결과들의 요약
+}
+
+impl Reorder {
+    fn receive(
+        &mut self,
+        message: Message,
+        printer: &mut Printer,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        match message {
+            Message::Chunk(index, bytes) if index == self.next => self.write(&bytes, printer, out),
+            Message::Chunk(index, bytes) => {
+                self.pending.entry(index).or_default().buf.extend(bytes);
+                Ok(())
+            }
+            Message::Finished(index, finish) => {
+                self.pending.entry(index).or_default().finish = Some(finish);
+                self.advance(printer, out)
+            }
+        }
+    }
+
+    // 순서가 된 파일의 모아 둔 출력을 쓰고, 검색을 마친 파일이면 다음 파일로 넘어감
+    fn advance(&mut self, printer: &mut Printer, out: &mut impl Write) -> io::Result<()> {
+        while let Some(pending) = self.pending.get_mut(&self.next) {
+            let buf = mem::take(&mut pending.buf);
+            let finish = pending.finish.take();
+            self.write(&buf, printer, out)?;
+            // 아직 검색 중이면 이후의 조각은 `receive`에서 바로 출력
+            let Some(finish) = finish else {
+                break;
+            };
+
+            self.pending.remove(&self.next);
+            self.next += 1;
+            self.started = false;
+            self.outcome.matched |= finish.matched;
+            if let Some(error) = finish.error {
+                eprintln!("minigrep: {error}");
+                self.outcome.failures += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn write(
+        &mut self,
+        bytes: &[u8],
+        printer: &mut Printer,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        // 파일 사이의 구분자는 파일마다 첫 조각 앞에만 출력
+        if self.started {
+            out.write_all(bytes)
+        } else {
+            self.started = true;
+            printer.print_buffered(out, bytes)
+        }
+    }
+}
+
+/// 입력들을 `config.threads`개의 스레드에서 병렬로 검색하고 입력 순서대로 출력
+pub fn search_parallel(
+    config: Config,
+    matcher: PatternMatcher,
+    candidates: Option<Candidates>,
+    printer: &mut Printer,
+    out: &mut impl Write,
+) -> io::Result<Outcome> {
+    let (jobs, receiver) = mpsc::channel();
+    // 여러 작업 스레드가 수신자를 공유하되, 한 번에 하나의 스레드만 파일을 가져감
+    let receiver = Mutex::new(receiver);
+    // 작업마다 새 `Printer`를 사용해 파일 사이의 구분자는 메인 스레드에서만 출력
+    let template = printer.clone();
+
+    thread::scope(|scope| {
+        let (sender, messages) = mpsc::channel();
+        for _ in 0..config.threads {
+            let (config, matcher, template, receiver) = (&config, &matcher, &template, &receiver);
+            let sender = sender.clone();
+            scope.spawn(move || work(config, matcher, template, receiver, sender));
+        }
+        drop(sender);
+
+        // 에러로 일찍 반환하더라도 두 채널이 닫히므로 작업 스레드는 곧 끝남
+        let mut reorder = Reorder {
+            pending: BTreeMap::new(),
+            next: 0,
+            started: false,
+            outcome: Outcome::default(),
+        };
+        for (index, input) in inputs(&config, candidates.as_ref()).enumerate() {
+            match input {
+                // 작업 스레드가 모두 끝났다면 (패닉) `scope`에서 다시 패닉
+                Ok(input) => {
+                    let _ = jobs.send((index, input));
+                }
+                Err(err) => {
+                    let finish = Finish {
+                        matched: false,
+                        error: Some(err.to_string()),
+                    };
+                    reorder.receive(Message::Finished(index, finish), printer, out)?;
+                }
+            }
+
+            // 그 사이에 도착한 출력이 있으면 바로 처리
+            while let Ok(message) = messages.try_recv() {
+                reorder.receive(message, printer, out)?;
+            }
+        }
+
+        // 남은 파일을 모두 검색해 송신자가 모두 사라지면 `iter`가 끝남
+        drop(jobs);
+        for message in messages.iter() {
+            reorder.receive(message, printer, out)?;
+        }
+        Ok(reorder.outcome)
+    })
+}
+
+// 채널이 닫히거나 메인 스레드가 먼저 끝날 때까지 파일을 하나씩 가져와 검색
+fn work(
+    config: &Config,
+    matcher: &PatternMatcher,
+    template: &Printer,
+    receiver: &Mutex<Receiver<(usize, Input)>>,
+    sender: Sender<Message>,
+) {
+    loop {
+        // 잠금은 파일을 꺼내는 동안에만 유지하고, 검색은 잠금을 해제한 뒤 실행
+        let job = receiver.lock().unwrap().recv();
+        let Ok((index, input)) = job else {
+            break;
+        };
+
+        let mut chunks = Chunks {
+            index,
+            buf: Vec::new(),
+            sender: &sender,
+        };
+        let mut printer = template.clone();
+        let (matched, error) =
+            match search_input(config, matcher, &mut printer, &mut chunks, &input) {
+                Ok(matched) => (matched, None),
+                Err(InputError::Read(err)) => {
+                    (false, Some(format!("{}: {err}", input.path().display())))
+                }
+                // 메인 스레드가 먼저 끝나 더 보낼 곳이 없음
+                Err(InputError::Write(_)) => break,
+            };
+        let finish = Finish { matched, error };
+        if chunks.flush().is_err() || sender.send(Message::Finished(index, finish)).is_err() {
+            break;
+        }
+    }
+}
+
+/// 출력을 `CHUNK_LEN`만큼 모아 메인 스레드로 보내는 `Write`
+struct Chunks<'a> {
+    index: usize,
+    buf: Vec<u8>,
+    sender: &'a Sender<Message>,
+}
+
+impl Write for Chunks<'_> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(bytes);
+        if self.buf.len() >= CHUNK_LEN {
+            self.flush()?;
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = Message::Chunk(self.index, mem::take(&mut self.buf));
+        self.sender
+            .send(chunk)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "search stopped"))
+    }
+}
@@ -0,0 +1,318 @@
+//! 검색 결과 출력
+use crate::color::{paint, Style};
+use crate::json::JsonPrinter;
+use crate::matcher::PatternMatcher;
+use crate::replace::Replacer;
+use crate::search::Event;
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// 출력 형식별 출력기
+#[derive(Clone)]
+pub enum Printer {
+    Standard(StandardPrinter), // grep과 같은 텍스트 형식
+    Json(JsonPrinter),         // `--json`: JSON Lines 형식
+}
+
+impl Printer {
+    /// 새 파일의 결과를 출력하기 전에 호출
+    pub fn begin_file(&mut self) {
+        match self {
+            Printer::Standard(printer) => printer.begin_file(),
+            Printer::Json(printer) => printer.begin_file(),
+        }
+    }
+
+    pub fn print(
+        &mut self,
+        out: &mut impl Write,
+        matcher: &PatternMatcher,
+        path: &Path,
+        event: &Event,
+    ) -> io::Result<()> {
+        match self {
+            Printer::Standard(printer) => printer.print(out, matcher, path, event),
+            Printer::Json(printer) => printer.print(out, matcher, path, event),
+        }
+    }
+
+    /// 파일 하나의 검색을 마친 뒤 호출
+    pub fn end_file(
+        &mut self,
+        out: &mut impl Write,
+        path: &Path,
+        bytes_searched: usize,
+        elapsed: Duration,
+    ) -> io::Result<()> {
+        match self {
+            Printer::Standard(_) => Ok(()),
+            Printer::Json(printer) => printer.end_file(out, path, bytes_searched, elapsed),
+        }
+    }
+
+    /// 다른 스레드에서 미리 출력해 둔 파일 하나의 결과를 그대로 씀
+    pub fn print_buffered(&mut self, out: &mut impl Write, buf: &[u8]) -> io::Result<()> {
+        match self {
+            Printer::Standard(printer) => printer.print_buffered(out, buf),
+            Printer::Json(_) => out.write_all(buf),
+        }
+    }
+
+    /// `-c`: 파일별로 선택된 라인 수 출력 (`--json`과 함께 사용할 수 없음)
+    pub fn print_count(
+        &mut self,
+        out: &mut impl Write,
+        path: &Path,
+        count: usize,
+    ) -> io::Result<()> {
+        match self {
+            Printer::Standard(printer) => printer.print_count(out, path, count),
+            Printer::Json(_) => Ok(()),
+        }
+    }
+
+    /// `-l`: 파일 경로만 출력 (`--json`과 함께 사용할 수 없음)
+    pub fn print_path(&mut self, out: &mut impl Write, path: &Path) -> io::Result<()> {
+        match self {
+            Printer::Standard(printer) => printer.print_path(out, path),
+            Printer::Json(_) => Ok(()),
+        }
+    }
+
+    /// 모든 검색을 마친 뒤 호출
+    pub fn finish(&mut self, out: &mut impl Write, elapsed: Duration) -> io::Result<()> {
+        match self {
+            Printer::Standard(_) => Ok(()),
+            Printer::Json(printer) => printer.finish(out, elapsed),
+        }
+    }
+}
+
+/// grep과 같은 텍스트 형식 출력기
+/// 일치 라인은 `경로:번호:오프셋:라인`, 문맥 라인은 `경로-번호-오프셋-라인` 형태로 출력
+#[derive(Clone)]
+pub struct StandardPrinter {
+    pub with_filename: bool,       // 결과 앞에 파일 경로 출력
+    pub line_number: bool,         // `-n`: 라인 번호 출력
+    pub byte_offset: bool,         // `-b`: 바이트 오프셋 출력
+    pub context: bool,             // 문맥 출력 여부 (파일 사이에도 `--` 구분자가 필요한지 판단)
+    pub replace: Option<Replacer>, // `--replace`: 일치 라인의 일치 부분을 바꿔서 출력
+    pub color: bool,               // `--color`: 경로, 번호, 일치 부분을 색상으로 구분
+    pub distance: bool,            // `--fuzzy`: 일치 라인의 편집 거리를 `~거리`로 출력
+    printed: bool,                 // 지금까지 출력한 라인이 있는지 여부
+    new_file: bool,                // 현재 파일에서 아직 아무것도 출력하지 않았는지 여부
+}
+
+impl StandardPrinter {
+    pub fn new(
+        with_filename: bool,
+        line_number: bool,
+        byte_offset: bool,
+        context: bool,
+    ) -> StandardPrinter {
+        StandardPrinter {
+            with_filename,
+            line_number,
+            byte_offset,
+            context,
+            replace: None,
+            color: false,
+            distance: false,
+            printed: false,
+            new_file: true,
+        }
+    }
+
+    pub fn begin_file(&mut self) {
+        self.new_file = true;
+    }
+
+    pub fn print(
+        &mut self,
+        out: &mut impl Write,
+        matcher: &PatternMatcher,
+        path: &Path,
+        event: &Event,
+    ) -> io::Result<()> {
+        let (separator, line_number, byte_offset, line, spans) = match event {
+            Event::Match(m) => (":", m.line_number, m.byte_offset, m.line, &m.spans[..]),
+            Event::Context(c) => ("-", c.line_number, c.byte_offset, c.line, &[][..]),
+            Event::Break => return self.print_break(out),
+        };
+
+        // 이전 파일의 문맥 그룹과 이번 파일의 문맥 그룹 사이에도 구분자 출력
+        if self.new_file {
+            self.new_file = false;
+            if self.context && self.printed {
+                self.print_break(out)?;
+            }
+        }
+
+        if self.with_filename {
+            self.field(out, Style::Path, &path.display().to_string(), separator)?;
+        }
+        if self.line_number {
+            self.field(out, Style::LineNumber, &line_number.to_string(), separator)?;
+        }
+        if self.byte_offset {
+            self.field(out, Style::LineNumber, &byte_offset.to_string(), separator)?;
+        }
+        // 라인에 일치가 여러 개면 가장 가까운 것의 거리
+        if self.distance {
+            let distance = spans
+                .iter()
+                .filter_map(|span| matcher.distance(&line[span.clone()]))
+                .min();
+            if let Some(distance) = distance {
+                self.field(out, Style::LineNumber, &format!("~{distance}"), separator)?;
+            }
+        }
+
+        // 일치 라인만 바꾸거나 강조하며, `-v`로 선택된 라인에는 일치 부분이 없음
+        let (line, spans) = match (event, &self.replace) {
+            (Event::Match(_), Some(replacer)) => match replacer.replace_spans(matcher, line) {
+                Some((replaced, spans)) => (Cow::Owned(replaced), Cow::Owned(spans)),
+                None => (Cow::Borrowed(line), Cow::Borrowed(&[][..])),
+            },
+            _ if self.color => (Cow::Borrowed(line), Cow::Borrowed(spans)),
+            _ => (Cow::Borrowed(line), Cow::Borrowed(&[][..])),
+        };
+
+        // UTF-8이 아닌 라인도 원본 바이트 그대로 출력
+        let mut last = 0;
+        for span in spans.iter() {
+            out.write_all(&line[last..span.start])?;
+            paint(out, self.color, Style::Match, &line[span.clone()])?;
+            last = span.end;
+        }
+        out.write_all(&line[last..])?;
+        out.write_all(b"\n")?;
+
+        self.printed = true;
+        Ok(())
+    }
+
+    // 경로, 번호 같은 앞부분 항목 하나와 구분자 출력
+    fn field(
+        &self,
+        out: &mut impl Write,
+        style: Style,
+        text: &str,
+        separator: &str,
+    ) -> io::Result<()> {
+        paint(out, self.color, style, text.as_bytes())?;
+        paint(out, self.color, Style::Separator, separator.as_bytes())
+    }
+
+    // 문맥 그룹 사이의 `--` 출력
+    fn print_break(&self, out: &mut impl Write) -> io::Result<()> {
+        paint(out, self.color, Style::Separator, b"--")?;
+        out.write_all(b"\n")
+    }
+
+    pub fn print_buffered(&mut self, out: &mut impl Write, buf: &[u8]) -> io::Result<()> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+        if self.context && self.printed {
+            self.print_break(out)?;
+        }
+        self.printed = true;
+        out.write_all(buf)
+    }
+
+    pub fn print_count(
+        &mut self,
+        out: &mut impl Write,
+        path: &Path,
+        count: usize,
+    ) -> io::Result<()> {
+        if self.with_filename {
+            self.field(out, Style::Path, &path.display().to_string(), ":")?;
+        }
+        writeln!(out, "{count}")
+    }
+
+    pub fn print_path(&mut self, out: &mut impl Write, path: &Path) -> io::Result<()> {
+        let path = path.display().to_string();
+        paint(out, self.color, Style::Path, path.as_bytes())?;
+        out.write_all(b"\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{Context, Match};
+    use crate::test_support;
+
+    fn print(printer: &mut StandardPrinter, args: &[&str], event: &Event) -> String {
+        let matcher = test_support::matcher(args);
+        let mut out = Vec::new();
+
+        printer
+            .print(&mut out, &matcher, Path::new("a.txt"), event)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn highlights_matches() {
+        let mut printer = StandardPrinter::new(true, true, false, true);
+        printer.color = true;
+        let args = ["o", "a.txt"];
+
+        let event = Event::Match(Match {
+            line_number: 3,
+            byte_offset: 10,
+            spans: vec![2..3, 5..6],
+            line: b"two dogs",
+        });
+        assert_eq!(
+            "\x1b[35ma.txt\x1b[0m\x1b[36m:\x1b[0m\x1b[32m3\x1b[0m\x1b[36m:\x1b[0m\
+             tw\x1b[1;31mo\x1b[0m d\x1b[1;31mo\x1b[0mgs\n",
+            print(&mut printer, &args, &event)
+        );
+
+        // 문맥 라인은 강조하지 않음
+        let event = Event::Context(Context {
+            line_number: 4,
+            byte_offset: 19,
+            line: b"no",
+        });
+        assert!(print(&mut printer, &args, &event).ends_with("\x1b[36m-\x1b[0mno\n"));
+    }
+
+    #[test]
+    fn fuzzy_distance_field() {
+        let mut printer = StandardPrinter::new(false, true, false, false);
+        printer.distance = true;
+
+        let event = Event::Match(Match {
+            line_number: 7,
+            byte_offset: 0,
+            spans: vec![5..14, 25..35],
+            line: b"lost conection, retrying connection",
+        });
+        assert_eq!(
+            "7:~0:lost conection, retrying connection\n",
+            print(&mut printer, &["--fuzzy", "1", "connection"], &event)
+        );
+    }
+
+    #[test]
+    fn plain_output_with_replacement() {
+        let mut printer = StandardPrinter::new(false, false, false, false);
+        printer.replace = Some(Replacer::new("0", false));
+
+        let event = Event::Match(Match {
+            line_number: 1,
+            byte_offset: 0,
+            spans: vec![2..3, 5..6],
+            line: b"two dogs",
+        });
+        assert_eq!("tw0 d0gs\n", print(&mut printer, &["o"], &event));
+    }
+}
@@ -0,0 +1,436 @@
+//! `--replace`: 일치한 부분을 템플릿으로 바꾸기
+//!
+//! 기본적으로는 바꾼 라인을 출력하기만 하고, `--in-place`이면 파일을 고쳐 씀
+//! 파일은 같은 디렉터리의 임시 파일에 다 쓴 뒤 이름을 바꾸므로,
+//! 중간에 실패해도 원본이 반쯤 바뀐 상태로 남지 않음
+//! `--dry-run`이면 파일을 건드리지 않고 바뀔 내용을 unified diff로 출력
+use crate::matcher::{Matcher, PatternMatcher};
+use crate::InputError;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+// diff에서 바뀐 라인 앞뒤로 보여 주는 문맥 라인 수
+const DIFF_CONTEXT: usize = 3;
+
+/// 바꿀 내용
+#[derive(Clone)]
+pub struct Replacer {
+    template: Vec<u8>,
+    // `--regex`이면 `$1`, `${name}` 같은 캡처 그룹 참조를 펼침
+    // 정규 표현식이 아닌 검색어에서는 템플릿을 그대로 사용
+    expand: bool,
+}
+
+impl Replacer {
+    pub fn new(template: &str, expand: bool) -> Replacer {
+        Replacer {
+            template: template.as_bytes().to_vec(),
+            expand,
+        }
+    }
+
+    /// 라인의 모든 일치 부분을 바꾼 결과 (바꿀 부분이 없으면 `None`)
+    pub fn replace(&self, matcher: &PatternMatcher, line: &[u8]) -> Option<Vec<u8>> {
+        self.replace_spans(matcher, line)
+            .map(|(replaced, _)| replaced)
+    }
+
+    /// `replace`와 같지만 바꾼 결과 안에서 템플릿이 들어간 범위들도 함께 반환 (강조 출력용)
+    pub fn replace_spans(
+        &self,
+        matcher: &PatternMatcher,
+        line: &[u8],
+    ) -> Option<(Vec<u8>, Vec<Range<usize>>)> {
+        // `-v`로 선택된 라인에는 일치한 부분이 없음
+        if matcher.is_inverted() {
+            return None;
+        }
+        let spans = matcher.find_all(line);
+        if spans.is_empty() {
+            return None;
+        }
+
+        let mut replaced = Vec::with_capacity(line.len());
+        let mut replaced_spans = Vec::with_capacity(spans.len());
+        let mut last = 0;
+        for span in spans {
+            replaced.extend_from_slice(&line[last..span.start]);
+            let start = replaced.len();
+            match self.expand.then(|| matcher.captures_at(line, span.start)) {
+                Some(Some(captures)) => captures.expand(&self.template, &mut replaced),
+                _ => replaced.extend_from_slice(&self.template),
+            }
+            replaced_spans.push(start..replaced.len());
+            last = span.end;
+        }
+        replaced.extend_from_slice(&line[last..]);
+
+        Some((replaced, replaced_spans))
+    }
+
+    /// 파일의 모든 일치 부분을 바꿔 쓰고, 일치한 라인이 있었는지 여부를 반환
+    /// `max_count`가 있으면 처음 그만큼의 일치한 라인만 바꾸고 나머지는 그대로 둠 (`-m`)
+    /// `dry_run`이면 파일은 그대로 두고 바뀔 내용을 `out`에 diff로 출력
+    /// 심볼릭 링크는 링크를 일반 파일로 바꾸지 않고 링크가 가리키는 파일을 고쳐 씀
+    pub fn edit(
+        &self,
+        matcher: &PatternMatcher,
+        path: &Path,
+        max_count: Option<usize>,
+        dry_run: bool,
+        out: &mut impl Write,
+    ) -> Result<bool, InputError> {
+        let mut reader = BufReader::new(File::open(path).map_err(InputError::Read)?);
+        if crate::walk::is_binary(reader.fill_buf().map_err(InputError::Read)?) {
+            return Ok(false);
+        }
+
+        // 원본과 같은 디렉터리에 만들어야 `rename`이 같은 파일 시스템 안에서 이루어짐
+        let mut temp = if dry_run {
+            None
+        } else {
+            let target = fs::canonicalize(path).map_err(InputError::Read)?;
+            let temp = NamedTempFile::new_in(target.parent().unwrap_or(Path::new("/")));
+            Some((target, BufWriter::new(temp.map_err(InputError::Read)?)))
+        };
+        let mut diff = Diff::new(path);
+        let mut selected = 0;
+        let mut changed = false;
+
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            if reader
+                .read_until(b'\n', &mut buf)
+                .map_err(InputError::Read)?
+                == 0
+            {
+                break;
+            }
+
+            // 줄바꿈 문자는 원본 그대로 유지
+            let body_len = buf.len() - line_terminator_len(&buf);
+            let replaced = if max_count.is_none_or(|max| selected < max) {
+                self.replace(matcher, &buf[..body_len])
+            } else {
+                None
+            };
+            let replaced = replaced.map(|mut replaced| {
+                replaced.extend_from_slice(&buf[body_len..]);
+                replaced
+            });
+
+            selected += usize::from(replaced.is_some());
+            changed |= replaced.as_ref().is_some_and(|replaced| *replaced != buf);
+            if let Some((_, temp)) = &mut temp {
+                let line = replaced.as_deref().unwrap_or(&buf);
+                temp.write_all(line).map_err(InputError::Read)?;
+            }
+            if dry_run {
+                diff.line(out, &buf, replaced.as_deref())
+                    .map_err(InputError::Write)?;
+            }
+        }
+
+        let matched = selected > 0;
+        if dry_run {
+            diff.finish(out).map_err(InputError::Write)?;
+            return Ok(matched);
+        }
+        // 바뀐 내용이 없으면 임시 파일은 `drop`되며 삭제됨
+        let (Some((target, temp)), true) = (temp, changed) else {
+            return Ok(matched);
+        };
+        let temp = temp
+            .into_inner()
+            .map_err(|err| InputError::Read(err.into_error()))?;
+        // 새 파일은 원본의 권한과 소유자를 이어받음
+        let metadata = fs::metadata(&target).map_err(InputError::Read)?;
+        fs::set_permissions(temp.path(), metadata.permissions()).map_err(InputError::Read)?;
+        copy_owner(&metadata, temp.path());
+        temp.persist(&target)
+            .map_err(|err| InputError::Read(err.error))?;
+
+        Ok(matched)
+    }
+}
+
+// 소유자를 바꿀 권한이 없으면 (다른 사용자의 파일을 고치는 경우) 임시 파일을 만든 사용자의 것으로 남음
+#[cfg(unix)]
+fn copy_owner(metadata: &fs::Metadata, path: &Path) {
+    use std::os::unix::fs::MetadataExt;
+    let _ = std::os::unix::fs::chown(path, Some(metadata.uid()), Some(metadata.gid()));
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_metadata: &fs::Metadata, _path: &Path) {}
+
+// 라인 끝의 `\n` 또는 `\r\n` 길이
+fn line_terminator_len(line: &[u8]) -> usize {
+    if line.ends_with(b"\r\n") {
+        2
+    } else if line.ends_with(b"\n") {
+        1
+    } else {
+        0
+    }
+}
+
+/// 파일 하나의 unified diff를 라인 단위로 만들어 가며 출력
+/// 바뀐 라인 주변의 hunk 하나만 메모리에 보관함
+struct Diff<'p> {
+    path: &'p Path,
+    changed: bool,   // 파일 헤더를 출력했는지 여부
+    old_line: usize, // 원본에서 지금까지 읽은 라인 수
+    new_line: usize, // 바뀐 내용에서 지금까지 쓴 라인 수
+    // hunk 밖에서 최근에 지나간 바뀌지 않은 라인들 (다음 hunk의 앞쪽 문맥)
+    recent: VecDeque<Vec<u8>>,
+    hunk: Option<Hunk>,
+}
+
+struct Hunk {
+    old_start: usize,
+    new_start: usize,
+    lines: Vec<(u8, Vec<u8>)>, // `(' ' | '-' | '+', 줄바꿈을 포함한 라인)`
+    unchanged: usize,          // 마지막으로 바뀐 라인 뒤에 이어진 바뀌지 않은 라인 수
+}
+
+impl<'p> Diff<'p> {
+    fn new(path: &'p Path) -> Diff<'p> {
+        Diff {
+            path,
+            changed: false,
+            old_line: 0,
+            new_line: 0,
+            recent: VecDeque::with_capacity(DIFF_CONTEXT),
+            hunk: None,
+        }
+    }
+
+    /// 원본 라인 하나와 바뀐 결과를 추가 (`replaced`가 `None`이면 바뀌지 않은 라인)
+    fn line(
+        &mut self,
+        out: &mut impl Write,
+        line: &[u8],
+        replaced: Option<&[u8]>,
+    ) -> io::Result<()> {
+        self.old_line += 1;
+
+        let Some(replaced) = replaced.filter(|replaced| *replaced != line) else {
+            self.new_line += 1;
+            match &mut self.hunk {
+                // 다음 바뀐 라인과의 사이가 앞뒤 문맥을 합친 것보다 멀면 hunk를 끝냄
+                Some(hunk) if hunk.unchanged == 2 * DIFF_CONTEXT => {
+                    self.flush(out)?;
+                    self.remember(line);
+                }
+                Some(hunk) => {
+                    hunk.lines.push((b' ', line.to_vec()));
+                    hunk.unchanged += 1;
+                }
+                None => self.remember(line),
+            }
+            return Ok(());
+        };
+
+        if !self.changed {
+            self.changed = true;
+            writeln!(out, "--- {}", self.path.display())?;
+            writeln!(out, "+++ {}", self.path.display())?;
+        }
+
+        let hunk = self.hunk.get_or_insert_with(|| {
+            let context = self.recent.len();
+            Hunk {
+                old_start: self.old_line - context,
+                new_start: self.new_line + 1 - context,
+                lines: self.recent.drain(..).map(|line| (b' ', line)).collect(),
+                unchanged: 0,
+            }
+        });
+        hunk.lines.push((b'-', line.to_vec()));
+        // 템플릿에 줄바꿈이 있으면 한 라인이 여러 라인이 될 수 있음
+        for new in replaced.split_inclusive(|&b| b == b'\n') {
+            hunk.lines.push((b'+', new.to_vec()));
+            self.new_line += 1;
+        }
+        hunk.unchanged = 0;
+
+        Ok(())
+    }
+
+    fn remember(&mut self, line: &[u8]) {
+        if self.recent.len() == DIFF_CONTEXT {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(line.to_vec());
+    }
+
+    /// 진행 중인 hunk를 출력
+    fn flush(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let Some(mut hunk) = self.hunk.take() else {
+            return Ok(());
+        };
+        // 뒤쪽 문맥은 `DIFF_CONTEXT`개까지만 출력하고, 나머지는 다음 hunk의 앞쪽 문맥이 됨
+        let extra = hunk.unchanged.saturating_sub(DIFF_CONTEXT);
+        self.recent.clear();
+        for (_, line) in hunk.lines.drain(hunk.lines.len() - extra..) {
+            self.recent.push_back(line);
+        }
+
+        let old_count = hunk.lines.iter().filter(|(tag, _)| *tag != b'+').count();
+        let new_count = hunk.lines.iter().filter(|(tag, _)| *tag != b'-').count();
+        writeln!(
+            out,
+            "@@ -{},{old_count} +{},{new_count} @@",
+            hunk.old_start, hunk.new_start
+        )?;
+        for (tag, line) in &hunk.lines {
+            out.write_all(&[*tag])?;
+            out.write_all(line)?;
+            if !line.ends_with(b"\n") {
+                out.write_all(b"\n\\ No newline at end of file\n")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut impl Write) -> io::Result<()> {
+        self.flush(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::matcher;
+    use std::{env, process};
+
+    #[test]
+    fn replaces_every_match() {
+        let matcher = matcher(&["-w", "frog"]);
+        let replacer = Replacer::new("toad", false);
+
+        assert_eq!(
+            Some(b"How public, like a toad, toads and toad".to_vec()),
+            replacer.replace(&matcher, b"How public, like a frog, toads and frog")
+        );
+        assert_eq!(None, replacer.replace(&matcher, b"frogs"));
+    }
+
+    #[test]
+    fn capture_references() {
+        let matcher = matcher(&["--regex", r"(?<user>\w+)@(\w+)"]);
+        let expanded = Replacer::new("$2 at ${user}", true);
+        let literal = Replacer::new("$2 at ${user}", false);
+
+        assert_eq!(
+            Some(b"mail example at alice, example at bob".to_vec()),
+            expanded.replace(&matcher, b"mail alice@example, bob@example")
+        );
+        assert_eq!(
+            Some(b"$2 at ${user}".to_vec()),
+            literal.replace(&matcher, b"alice@example")
+        );
+    }
+
+    #[test]
+    fn edits_in_place_and_previews_diff() {
+        let path = env::temp_dir().join(format!("minigrep-replace-{}.txt", process::id()));
+        let mut original = String::new();
+        for i in 1..=12 {
+            original.push_str(&format!("line {i}\r\n"));
+        }
+        original.push_str("last frog");
+        fs::write(&path, &original).unwrap();
+
+        let matcher = matcher(&["--regex", "^line (2|4)$|frog"]);
+        let replacer = Replacer::new("changed", false);
+
+        let mut diff = Vec::new();
+        replacer
+            .edit(&matcher, &path, None, true, &mut diff)
+            .ok()
+            .unwrap();
+        assert_eq!(original, fs::read_to_string(&path).unwrap());
+
+        let header = format!("--- {0}\n+++ {0}\n", path.display());
+        let expected = "\
+@@ -1,7 +1,7 @@
+ line 1\r
+-line 2\r
++changed\r
+ line 3\r
+-line 4\r
++changed\r
+ line 5\r
+ line 6\r
+ line 7\r
+@@ -10,4 +10,4 @@
+ line 10\r
+ line 11\r
+ line 12\r
+-last frog
+\\ No newline at end of file
++last changed
+\\ No newline at end of file
+";
+        assert_eq!(header + expected, String::from_utf8(diff).unwrap());
+
+        replacer
+            .edit(&matcher, &path, None, false, &mut io::sink())
+            .ok()
+            .unwrap();
+        let edited = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(edited.starts_with("line 1\r\nchanged\r\nline 3\r\nchanged\r\n"));
+        assert!(edited.ends_with("line 12\r\nlast changed"));
+    }
+
+    #[test]
+    fn in_place_stops_at_max_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frogs.txt");
+        fs::write(&path, "frog\ntoad\nfrog\nfrog\n").unwrap();
+
+        let matcher = matcher(&["frog"]);
+        let replacer = Replacer::new("newt", false);
+        assert!(replacer
+            .edit(&matcher, &path, Some(2), false, &mut io::sink())
+            .ok()
+            .unwrap());
+        assert_eq!(
+            "newt\ntoad\nnewt\nfrog\n",
+            fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn edits_symlink_target_and_keeps_permissions() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let (target, link) = (dir.path().join("script.sh"), dir.path().join("link.sh"));
+        fs::write(&target, "echo frog\n").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o750)).unwrap();
+        symlink(&target, &link).unwrap();
+
+        let matcher = matcher(&["frog"]);
+        Replacer::new("toad", false)
+            .edit(&matcher, &link, None, false, &mut io::sink())
+            .ok()
+            .unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!("echo toad\n", fs::read_to_string(&target).unwrap());
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(0o750, mode & 0o777);
+    }
+}
@@ -0,0 +1,646 @@
+//! 일치 라인과 문맥 라인 찾기
+//!
+//! 입력을 블록 단위로 읽어 블록 안의 완전한 라인들만 검색하고 바로 결과를 전달하므로,
+//! 파일 크기와 상관없이 블록 크기(또는 가장 긴 라인) 정도의 메모리만 사용함
+//! 문자열 검색은 라인마다 확인하지 않고 블록 전체에서 검색어를 찾은 뒤,
+//! 찾은 위치 주변에서만 라인 경계를 구함
+use crate::matcher::{Matcher, PatternMatcher};
+use memchr::{memchr, memchr_iter, memrchr};
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read};
+use std::ops::Range;
+
+// 한 번에 읽는 블록 크기 (이보다 긴 라인을 만나면 버퍼를 늘림)
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// 일치한 라인 하나
+#[derive(Debug, PartialEq)]
+pub struct Match<'a> {
+    pub line_number: usize,       // 1부터 시작하는 라인 번호
+    pub byte_offset: usize,       // 입력 시작부터 라인 시작까지의 바이트 오프셋
+    pub spans: Vec<Range<usize>>, // 라인 안에서 일치한 부분들의 바이트 범위 (`-v`, `--field`이면 비어 있음)
+    pub line: &'a [u8],           // 줄바꿈 문자를 제외한 라인 내용 (UTF-8이 아닐 수도 있음)
+}
+
+/// 일치 라인 주변에 함께 출력하는 문맥 라인 하나
+#[derive(Debug, PartialEq)]
+pub struct Context<'a> {
+    pub line_number: usize,
+    pub byte_offset: usize,
+    pub line: &'a [u8],
+}
+
+/// 검색 중에 발생하는 사건
+#[derive(Debug, PartialEq)]
+pub enum Event<'a> {
+    Match(Match<'a>),
+    Context(Context<'a>),
+    Break, // 서로 떨어진 문맥 그룹 사이의 구분 (`--`)
+}
+
+// `str::lines`와 같이 `\n`, `\r\n`을 모두 줄바꿈으로 취급
+fn trim_line_terminator(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+// `pos`에서 시작하는 라인의 끝 (줄바꿈 문자 다음 위치)
+fn line_end(region: &[u8], pos: usize) -> usize {
+    memchr(b'\n', &region[pos..]).map_or(region.len(), |index| pos + index + 1)
+}
+
+// 마지막 `n`개 라인이 시작하는 위치
+fn tail_start(region: &[u8], n: usize) -> usize {
+    if n == 0 {
+        return region.len();
+    }
+    // 마지막 줄바꿈은 마지막 라인의 끝이므로 제외하고 찾음
+    let mut end = region.len() - usize::from(region.ends_with(b"\n"));
+    for _ in 0..n {
+        match memrchr(b'\n', &region[..end]) {
+            Some(index) => end = index,
+            None => return 0,
+        }
+    }
+    end + 1
+}
+
+/// 메모리에 있는 내용에서 모든 일치 라인을 위치 정보와 함께 반환
+pub fn find_matches<'a, M: Matcher + ?Sized>(matcher: &M, contents: &'a [u8]) -> Vec<Match<'a>> {
+    let mut results = Vec::new();
+
+    // `&[u8]`도 `Read`이므로 같은 검색 과정을 사용하고,
+    // 결과 라인은 원본 내용을 가리키도록 다시 슬라이스함
+    let searched = search_reader(matcher, contents, 0, 0, None, |event| {
+        if let Event::Match(m) = event {
+            let line = &contents[m.byte_offset..m.byte_offset + m.line.len()];
+            results.push(Match { line, ..m });
+        }
+        Ok(true)
+    });
+    searched.expect("reading from memory never fails");
+
+    results
+}
+
+/// 입력을 읽으며 일치 라인과 전후 `before`/`after`개의 문맥 라인을 차례로 `sink`에 전달
+///
+/// 겹치거나 맞닿은 문맥 구간은 하나로 합쳐지며,
+/// 떨어진 구간 사이에는 `Event::Break`가 전달됨
+/// `max_count`개의 라인을 선택하면 뒤쪽 문맥까지만 전달하고 검색을 멈춤 (`-m`)
+/// `sink`가 `Ok(false)`를 반환하면 검색을 멈춤
+/// 검색을 마칠 때까지 읽은 바이트 수를 반환
+pub fn search_reader<M, R, F>(
+    matcher: &M,
+    reader: R,
+    before: usize,
+    after: usize,
+    max_count: Option<usize>,
+    sink: F,
+) -> io::Result<usize>
+where
+    M: Matcher + ?Sized,
+    R: Read,
+    F: FnMut(Event<'_>) -> io::Result<bool>,
+{
+    let prefilter = matcher.scans_buffer().then_some(matcher);
+    let select = |line: &[u8]| matcher.select(line);
+    Scanner::new(prefilter, select, before, after, max_count, sink).run(reader)
+}
+
+/// `search_reader`와 같지만 라인이 선택되는지를 `select`로 판단 (`--field`, `--column`)
+pub(crate) fn search_reader_by<R, S, F>(
+    reader: R,
+    before: usize,
+    after: usize,
+    max_count: Option<usize>,
+    select: S,
+    sink: F,
+) -> io::Result<usize>
+where
+    R: Read,
+    S: FnMut(&[u8]) -> Option<Vec<Range<usize>>>,
+    F: FnMut(Event<'_>) -> io::Result<bool>,
+{
+    Scanner::new(
+        None::<&PatternMatcher>,
+        select,
+        before,
+        after,
+        max_count,
+        sink,
+    )
+    .run(reader)
+}
+
+/// `-U`: 입력 전체를 읽은 뒤 라인 경계를 넘는 일치를 찾음
+///
+/// 일치가 걸친 라인은 모두 선택되며 (`-v`이면 어느 일치에도 걸치지 않은 라인이 선택됨),
+/// 라인 번호, 문맥, `max_count`, `sink`는 `search_reader`와 같음
+/// 일치가 어디까지 이어질지 알 수 없으므로 블록 단위가 아닌 입력 전체를 메모리에 올림
+pub fn search_multiline<M, R, F>(
+    matcher: &M,
+    mut reader: R,
+    before: usize,
+    after: usize,
+    max_count: Option<usize>,
+    sink: F,
+) -> io::Result<usize>
+where
+    M: Matcher + ?Sized,
+    R: Read,
+    F: FnMut(Event<'_>) -> io::Result<bool>,
+{
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents)?;
+
+    let mut matched = matched_lines(matcher, &contents).into_iter().peekable();
+    let mut index = 0;
+    let select = |line: &[u8]| {
+        // `\r\n`의 `\r`처럼 라인 끝에서 잘린 부분은 범위에서도 제외
+        let spans = matched
+            .next_if(|(matched, _)| *matched == index)
+            .map(|(_, spans)| {
+                spans
+                    .into_iter()
+                    .map(|span| span.start.min(line.len())..span.end.min(line.len()))
+                    .collect()
+            });
+        index += 1;
+        match (spans, matcher.is_inverted()) {
+            (Some(spans), false) => Some(spans),
+            (None, true) => Some(Vec::new()),
+            _ => None,
+        }
+    };
+    search_reader_by(&contents[..], before, after, max_count, select, sink)
+}
+
+// 일치가 걸친 라인마다 (0부터 시작하는 라인 번호, 라인 안에서 일치한 부분들의 범위)
+fn matched_lines<M: Matcher + ?Sized>(
+    matcher: &M,
+    contents: &[u8],
+) -> Vec<(usize, Vec<Range<usize>>)> {
+    let starts: Vec<usize> = [0]
+        .into_iter()
+        .chain(memchr_iter(b'\n', contents).map(|index| index + 1))
+        .collect();
+    let line_of = |offset: usize| starts.partition_point(|&start| start <= offset) - 1;
+
+    let mut lines: Vec<(usize, Vec<Range<usize>>)> = Vec::new();
+    for span in matcher.find_all(contents) {
+        // 줄바꿈으로 끝나는 일치는 그 줄바꿈이 있는 라인까지만 걸침
+        let first = line_of(span.start);
+        let last = line_of(span.end.saturating_sub(1).max(span.start));
+
+        for (index, &start) in starts.iter().enumerate().take(last + 1).skip(first) {
+            // 앞의 일치가 끝난 라인에서 시작하는 일치는 같은 라인의 범위 목록에 추가
+            if lines.last().is_none_or(|(last, _)| *last != index) {
+                lines.push((index, Vec::new()));
+            }
+            let spans = &mut lines.last_mut().unwrap().1;
+            spans.push(span.start.max(start) - start..span.end - start);
+        }
+    }
+
+    lines
+}
+
+/// 블록 사이에 유지해야 하는 검색 상태
+struct Scanner<'m, M: ?Sized, S, F> {
+    // 블록 전체에서 후보 위치를 찾을 수 있는 검색기 (후보가 없는 라인은 `select`를 건너뜀)
+    prefilter: Option<&'m M>,
+    select: S,
+    before: usize,
+    after: usize,
+    max_count: Option<usize>,
+    selected: usize, // 지금까지 선택한 라인 수
+    sink: F,
+    // 아직 출력하지 않은 직전 라인들 (최대 `before`개, `(번호, 오프셋, 내용)`)
+    pending: VecDeque<(usize, usize, Vec<u8>)>,
+    // 마지막 일치 라인 이후에 더 출력해야 하는 문맥 라인 수
+    remaining_after: usize,
+    // 마지막으로 전달한 라인 번호 (구간이 떨어졌는지 판단할 때 사용)
+    last_emitted: Option<usize>,
+    line_number: usize, // 마지막으로 처리한 라인 번호
+    offset: usize,      // 지금까지 처리한 바이트 수
+}
+
+impl<'m, M, S, F> Scanner<'m, M, S, F>
+where
+    M: Matcher + ?Sized,
+    S: FnMut(&[u8]) -> Option<Vec<Range<usize>>>,
+    F: FnMut(Event<'_>) -> io::Result<bool>,
+{
+    fn new(
+        prefilter: Option<&'m M>,
+        select: S,
+        before: usize,
+        after: usize,
+        max_count: Option<usize>,
+        sink: F,
+    ) -> Self {
+        Scanner {
+            prefilter,
+            select,
+            before,
+            after,
+            max_count,
+            selected: 0,
+            sink,
+            pending: VecDeque::with_capacity(before),
+            remaining_after: 0,
+            last_emitted: None,
+            line_number: 0,
+            offset: 0,
+        }
+    }
+
+    /// 입력을 끝까지 (또는 검색을 멈출 때까지) 검색하고 읽은 바이트 수를 반환
+    fn run(mut self, mut reader: impl Read) -> io::Result<usize> {
+        // 블록 버퍼는 재사용하며, 앞부분에는 이전 블록에서 끝나지 않은 라인이 남아 있음
+        let mut buf = vec![0; BLOCK_SIZE];
+        let mut len = 0;
+
+        loop {
+            if len == buf.len() {
+                buf.resize(buf.len() * 2, 0);
+            }
+            let read = match reader.read(&mut buf[len..]) {
+                Ok(read) => read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            len += read;
+
+            // 입력이 끝나지 않았다면 마지막 줄바꿈까지만 검색
+            let end = if read == 0 {
+                len
+            } else {
+                match memrchr(b'\n', &buf[..len]) {
+                    Some(index) => index + 1,
+                    None => continue,
+                }
+            };
+
+            if !self.search(&buf[..end])? || read == 0 {
+                return Ok(self.offset);
+            }
+            buf.copy_within(end..len, 0);
+            len -= end;
+        }
+    }
+
+    /// 완전한 라인들로 이루어진 블록을 검색
+    /// `sink`가 검색을 멈추게 하면 `Ok(false)`
+    fn search(&mut self, block: &[u8]) -> io::Result<bool> {
+        if self.is_finished() {
+            return Ok(false);
+        }
+        let mut pos = 0;
+
+        while pos < block.len() {
+            if let Some(prefilter) = self.prefilter {
+                // 후보가 없는 라인들은 검색어를 확인하지 않고 건너뜀
+                let Some(hit) = prefilter.find_candidate(&block[pos..]) else {
+                    return self.skip(&block[pos..]);
+                };
+                let hit = pos + hit;
+                let start = memrchr(b'\n', &block[pos..hit]).map_or(pos, |index| pos + index + 1);
+                if !self.skip(&block[pos..start])? {
+                    return Ok(false);
+                }
+                pos = start;
+            }
+
+            let end = line_end(block, pos);
+            let raw = &block[pos..end];
+            let line = trim_line_terminator(raw);
+            let selected = (self.select)(line);
+            if !self.line(raw.len(), line, selected)? {
+                return Ok(false);
+            }
+            pos = end;
+        }
+
+        Ok(true)
+    }
+
+    // 최대 개수만큼 선택했고 뒤쪽 문맥도 모두 전달했는지 여부
+    fn is_finished(&self) -> bool {
+        self.remaining_after == 0 && self.max_count.is_some_and(|max| self.selected >= max)
+    }
+
+    /// 선택되지 않는 것이 확실한 라인들을 처리
+    /// 문맥으로 출력될 수 있는 앞뒤 라인만 하나씩 처리하고 나머지는 라인 수만 셈
+    fn skip(&mut self, mut region: &[u8]) -> io::Result<bool> {
+        while self.remaining_after > 0 && !region.is_empty() {
+            let end = line_end(region, 0);
+            if !self.line(end, trim_line_terminator(&region[..end]), None)? {
+                return Ok(false);
+            }
+            region = &region[end..];
+        }
+        if self.is_finished() {
+            return Ok(false);
+        }
+
+        let tail = tail_start(region, self.before);
+        let (skipped, tail) = region.split_at(tail);
+        if !skipped.is_empty() {
+            // 건너뛴 라인보다 앞선 라인은 더 이상 문맥이 될 수 없음
+            self.pending.clear();
+            self.line_number += memchr_iter(b'\n', skipped).count();
+            // 입력 마지막 라인에는 줄바꿈이 없을 수 있음
+            self.line_number += usize::from(!skipped.ends_with(b"\n"));
+            self.offset += skipped.len();
+        }
+
+        let mut pos = 0;
+        while pos < tail.len() {
+            let end = line_end(tail, pos);
+            if !self.line(end - pos, trim_line_terminator(&tail[pos..end]), None)? {
+                return Ok(false);
+            }
+            pos = end;
+        }
+
+        Ok(true)
+    }
+
+    /// 라인 하나를 처리 (`selected`는 `PatternMatcher::select`의 결과)
+    fn line(
+        &mut self,
+        len: usize,
+        line: &[u8],
+        selected: Option<Vec<Range<usize>>>,
+    ) -> io::Result<bool> {
+        self.line_number += 1;
+        let line_number = self.line_number;
+        let byte_offset = self.offset;
+        self.offset += len;
+
+        // 최대 개수만큼 선택한 뒤의 라인은 뒤쪽 문맥으로만 전달
+        let selected = selected.filter(|_| self.max_count.is_none_or(|max| self.selected < max));
+        if let Some(spans) = selected {
+            // 구분자는 문맥 출력을 사용할 때만 필요
+            let first = self
+                .pending
+                .front()
+                .map_or(line_number, |(number, _, _)| *number);
+            if self.before + self.after > 0
+                && self.last_emitted.is_some_and(|last| last + 1 < first)
+                && !(self.sink)(Event::Break)?
+            {
+                return Ok(false);
+            }
+            for (line_number, byte_offset, line) in self.pending.drain(..) {
+                let context = Context {
+                    line_number,
+                    byte_offset,
+                    line: &line,
+                };
+                if !(self.sink)(Event::Context(context))? {
+                    return Ok(false);
+                }
+            }
+
+            self.last_emitted = Some(line_number);
+            self.remaining_after = self.after;
+            self.selected += 1;
+            let m = Match {
+                line_number,
+                byte_offset,
+                spans,
+                line,
+            };
+            Ok((self.sink)(Event::Match(m))? && !self.is_finished())
+        } else if self.remaining_after > 0 {
+            self.last_emitted = Some(line_number);
+            self.remaining_after -= 1;
+            let context = Context {
+                line_number,
+                byte_offset,
+                line,
+            };
+            Ok((self.sink)(Event::Context(context))? && !self.is_finished())
+        } else {
+            if self.before > 0 {
+                // 가장 오래된 라인의 버퍼를 재사용해 메모리 사용량을 일정하게 유지
+                let mut entry = if self.pending.len() == self.before {
+                    self.pending.pop_front().unwrap()
+                } else {
+                    (0, 0, Vec::new())
+                };
+                entry.0 = line_number;
+                entry.1 = byte_offset;
+                entry.2.clear();
+                entry.2.extend_from_slice(line);
+                self.pending.push_back(entry);
+            }
+            Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::matcher;
+
+    const CONTENTS: &str = "\
+one
+two match
+three
+four
+five
+six match
+seven match
+eight
+nine
+ten match";
+
+    fn numbers(before: usize, after: usize, max_count: Option<usize>) -> Vec<String> {
+        let matcher = matcher(&["match"]);
+        let mut results = Vec::new();
+
+        search_reader(
+            &matcher,
+            CONTENTS.as_bytes(),
+            before,
+            after,
+            max_count,
+            |event| {
+                results.push(match event {
+                    Event::Match(m) => format!("{}:", m.line_number),
+                    Event::Context(c) => format!("{}-", c.line_number),
+                    Event::Break => "--".to_string(),
+                });
+                Ok(true)
+            },
+        )
+        .unwrap();
+
+        results
+    }
+
+    #[test]
+    // 라인 하나의 일치 범위 목록이며 범위 전체를 담은 `Vec`이 아님
+    #[allow(clippy::single_range_in_vec_init)]
+    fn match_positions() {
+        let matcher = matcher(&["match"]);
+        let matches = find_matches(&matcher, b"one\r\ntwo match\n");
+
+        assert_eq!(
+            vec![Match {
+                line_number: 2,
+                byte_offset: 5,
+                spans: vec![4..9],
+                line: b"two match",
+            }],
+            matches
+        );
+    }
+
+    #[test]
+    fn merges_overlapping_context() {
+        assert_eq!(
+            vec!["1-", "2:", "3-", "--", "5-", "6:", "7:", "8-", "9-", "10:"],
+            numbers(1, 1, None)
+        );
+    }
+
+    #[test]
+    fn separates_distant_groups() {
+        assert_eq!(
+            vec!["2:", "3-", "--", "6:", "7:", "8-", "--", "10:"],
+            numbers(0, 1, None)
+        );
+    }
+
+    #[test]
+    fn stops_after_max_count() {
+        // 마지막으로 선택한 라인의 뒤쪽 문맥은 일치하더라도 문맥으로 전달
+        assert_eq!(vec!["2:", "3-", "--", "6:", "7-"], numbers(0, 1, Some(2)));
+        assert_eq!(vec!["1-", "2:"], numbers(1, 0, Some(1)));
+        assert!(numbers(0, 0, Some(0)).is_empty());
+    }
+
+    #[test]
+    fn invalid_utf8_lines() {
+        let matcher = matcher(&["match"]);
+        let matches = find_matches(&matcher, b"\xff\xfe match\nno\n\xc3\x28 match");
+
+        assert_eq!(
+            vec![1, 3],
+            matches.iter().map(|m| m.line_number).collect::<Vec<_>>()
+        );
+        assert_eq!(&b"\xc3\x28 match"[..], matches[1].line);
+    }
+
+    #[test]
+    fn stops_when_sink_declines() {
+        let matcher = matcher(&["match"]);
+        let mut seen = 0;
+
+        search_reader(&matcher, CONTENTS.as_bytes(), 0, 0, None, |_| {
+            seen += 1;
+            Ok(false)
+        })
+        .unwrap();
+
+        assert_eq!(1, seen);
+    }
+
+    #[test]
+    fn literal_scan_matches_line_by_line() {
+        // 블록 경계를 여러 번 넘고, 블록보다 긴 라인도 포함
+        let mut contents = Vec::new();
+        for i in 0..20_000 {
+            match i % 97 {
+                0 => contents.extend_from_slice(b"a match here\r\n"),
+                1 => contents.extend_from_slice(b"matchless\n"),
+                _ => contents.extend_from_slice(format!("line {i}\n").as_bytes()),
+            }
+        }
+        contents.extend(vec![b'x'; BLOCK_SIZE * 2]);
+        contents.extend_from_slice(b" match");
+
+        let literal = matcher(&["-w", "match"]);
+        let regex = matcher(&["--regex", "-w", "match"]);
+        assert!(literal.scans_buffer() && !regex.scans_buffer());
+
+        let events = |matcher: &PatternMatcher| {
+            let mut results = Vec::new();
+            let searched = search_reader(matcher, &contents[..], 2, 3, None, |event| {
+                results.push(format!("{event:?}"));
+                Ok(true)
+            });
+            (searched.unwrap(), results)
+        };
+        let (searched, results) = events(&literal);
+
+        assert_eq!(contents.len(), searched);
+        assert_eq!(events(&regex), (searched, results.clone()));
+        // 마지막의 긴 라인 (`-w`이므로 `matchless`는 제외)
+        assert!(results
+            .last()
+            .unwrap()
+            .starts_with("Match(Match { line_number: 20001,"));
+    }
+
+    #[test]
+    fn multiline_blocks() {
+        let contents =
+            "fn main() {\n    panic!(\"boom\");\n}\nthread 'main' panicked\n  at src/main.rs\nok\n";
+        let run = |args: &[&str], after: usize| {
+            let matcher = matcher(args);
+            let mut results = Vec::new();
+            search_multiline(&matcher, contents.as_bytes(), 0, after, None, |event| {
+                results.push(match event {
+                    Event::Match(m) => format!("{}:{:?}", m.line_number, m.spans),
+                    Event::Context(c) => format!("{}-", c.line_number),
+                    Event::Break => "--".to_string(),
+                });
+                Ok(true)
+            })
+            .unwrap();
+            results
+        };
+
+        // 일치가 걸친 라인 전체가 선택되며, 줄바꿈으로 끝나는 일치는 다음 라인을 포함하지 않음
+        assert_eq!(
+            vec!["4:[14..22]", "5:[0..16]"],
+            run(&["-U", "--regex", r"panicked\n\s+at .*\n"], 0)
+        );
+        assert_eq!(
+            vec!["1:[10..11]", "2:[0..19]", "3:[0..1]", "4-"],
+            run(&["-U", "--regex", r"\{\n[^}]*\}"], 1)
+        );
+        assert_eq!(
+            vec!["4:[]", "5:[]", "6:[]"],
+            run(&["-U", "-v", "--regex", r"\{\n[^}]*\}"], 0)
+        );
+        // 한 라인에 일치가 여러 개면 범위도 모두 전달
+        assert_eq!(
+            vec!["1:[7..8, 8..9]", "2:[10..11, 17..18]"],
+            run(&["-U", "--regex", r"\(|\)"], 0)
+        );
+    }
+
+    #[test]
+    // 라인 하나의 일치 범위 목록이며 범위 전체를 담은 `Vec`이 아님
+    #[allow(clippy::single_range_in_vec_init)]
+    fn multiple_literals() {
+        let matcher = matcher(&["-e", "three", "-e", "two"]);
+        let matches = find_matches(&matcher, CONTENTS.as_bytes());
+
+        assert_eq!(
+            vec![(2, vec![0..3]), (3, vec![0..5])],
+            matches
+                .iter()
+                .map(|m| (m.line_number, m.spans.clone()))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! 다른 크레이트에서 쓰는 검색 API
+//!
+//! `Matcher`로 일치 위치를 찾고, `Searcher`가 파일, 리더, 메모리 버퍼를 라인 단위로 검색하며,
+//! 결과는 표준 출력 대신 `Sink`로 전달됨
+//!
+//! ```no_run
+//! use minigrep::{Match, PatternMatcher, Searcher, Sink};
+//! use std::io;
+//!
+//! struct Count(usize);
+//!
+//! impl Sink for Count {
+//!     fn matched(&mut self, _m: &Match) -> io::Result<bool> {
+//!         self.0 += 1;
+//!         Ok(true)
+//!     }
+//! }
+//!
+//! let mut count = Count(0);
+//! Searcher::new().search_path(&PatternMatcher::literal("ERROR"), "app.log", &mut count)?;
+//! # Ok::<(), io::Error>(())
+//! ```
+use crate::matcher::Matcher;
+use crate::search::{self, Context, Event, Match};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// 검색 결과를 받는 쪽
+/// 각 메서드가 `Ok(false)`를 반환하면 검색을 멈춤
+pub trait Sink {
+    /// 선택된 라인
+    fn matched(&mut self, m: &Match<'_>) -> io::Result<bool>;
+
+    /// 선택된 라인 주변의 문맥 라인
+    fn context(&mut self, context: &Context<'_>) -> io::Result<bool> {
+        let _ = context;
+        Ok(true)
+    }
+
+    /// 서로 떨어진 문맥 그룹 사이
+    fn context_break(&mut self) -> io::Result<bool> {
+        Ok(true)
+    }
+
+    /// 입력 하나의 검색을 마친 뒤 (중간에 멈춘 경우도 포함)
+    fn finish(&mut self, summary: &Summary) -> io::Result<()> {
+        let _ = summary;
+        Ok(())
+    }
+}
+
+/// 입력 하나의 검색 결과 요약
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub matched_lines: usize,  // 선택된 라인 수
+    pub bytes_searched: usize, // 검색을 마칠 때까지 읽은 바이트 수
+}
+
+/// 라인 단위 검색 방법 (문맥 라인 수, 최대 선택 라인 수, 여러 라인 모드)
+/// 한 번 만들어 여러 입력에 재사용할 수 있음
+#[derive(Clone, Debug, Default)]
+pub struct Searcher {
+    pub before_context: usize,    // `-B`
+    pub after_context: usize,     // `-A`
+    pub max_count: Option<usize>, // `-m`: 입력마다 이만큼 선택하면 멈춤
+    pub multiline: bool, // `-U`: 일치가 여러 라인에 걸칠 수 있음 (입력 전체를 메모리에 올림)
+}
+
+impl Searcher {
+    pub fn new() -> Searcher {
+        Searcher::default()
+    }
+
+    /// 파일을 열어 검색
+    pub fn search_path<M, S>(
+        &self,
+        matcher: &M,
+        path: impl AsRef<Path>,
+        sink: &mut S,
+    ) -> io::Result<()>
+    where
+        M: Matcher + ?Sized,
+        S: Sink + ?Sized,
+    {
+        self.search_reader(matcher, File::open(path)?, sink)
+    }
+
+    /// 메모리에 있는 내용을 검색
+    pub fn search_slice<M, S>(&self, matcher: &M, contents: &[u8], sink: &mut S) -> io::Result<()>
+    where
+        M: Matcher + ?Sized,
+        S: Sink + ?Sized,
+    {
+        self.search_reader(matcher, contents, sink)
+    }
+
+    /// 리더를 끝까지 읽으며 검색 (표준 입력, 압축 해제 스트림 등)
+    pub fn search_reader<M, R, S>(&self, matcher: &M, reader: R, sink: &mut S) -> io::Result<()>
+    where
+        M: Matcher + ?Sized,
+        R: Read,
+        S: Sink + ?Sized,
+    {
+        let mut matched_lines = 0;
+        let forward = |event: Event<'_>| match event {
+            Event::Match(m) => {
+                matched_lines += 1;
+                sink.matched(&m)
+            }
+            Event::Context(context) => sink.context(&context),
+            Event::Break => sink.context_break(),
+        };
+
+        let (before, after) = (self.before_context, self.after_context);
+        let bytes_searched = if self.multiline {
+            search::search_multiline(matcher, reader, before, after, self.max_count, forward)?
+        } else {
+            search::search_reader(matcher, reader, before, after, self.max_count, forward)?
+        };
+
+        sink.finish(&Summary {
+            matched_lines,
+            bytes_searched,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::PatternMatcher;
+    use std::ops::Range;
+
+    // 선택된 라인을 모으는 `Sink`
+    #[derive(Default)]
+    struct Collect {
+        lines: Vec<(usize, String)>,
+        summary: Summary,
+    }
+
+    impl Sink for Collect {
+        fn matched(&mut self, m: &Match<'_>) -> io::Result<bool> {
+            let line = String::from_utf8_lossy(m.line).into_owned();
+            self.lines.push((m.line_number, line));
+            Ok(true)
+        }
+
+        fn finish(&mut self, summary: &Summary) -> io::Result<()> {
+            self.summary = *summary;
+            Ok(())
+        }
+    }
+
+    // 직접 구현한 `Matcher`: 숫자로만 이루어진 단어
+    struct Digits;
+
+    impl Matcher for Digits {
+        fn find_at(&self, line: &[u8], start: usize) -> Option<Range<usize>> {
+            let begin = start + line[start..].iter().position(u8::is_ascii_digit)?;
+            let len = line[begin..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+            Some(begin..begin + len)
+        }
+    }
+
+    const LOG: &[u8] = b"start\nERROR 42\nretry\nEROR 7\nstop\n";
+
+    #[test]
+    fn collects_with_any_matcher() {
+        let searcher = Searcher::new();
+
+        let mut literal = Collect::default();
+        searcher
+            .search_slice(&PatternMatcher::literal("ERROR"), LOG, &mut literal)
+            .unwrap();
+        assert_eq!(vec![(2, "ERROR 42".to_string())], literal.lines);
+        assert_eq!(
+            Summary {
+                matched_lines: 1,
+                bytes_searched: LOG.len()
+            },
+            literal.summary
+        );
+
+        let mut fuzzy = Collect::default();
+        searcher
+            .search_slice(&PatternMatcher::fuzzy("ERROR", 1), LOG, &mut fuzzy)
+            .unwrap();
+        assert_eq!(2, fuzzy.summary.matched_lines);
+
+        let mut digits = Collect::default();
+        searcher.search_slice(&Digits, LOG, &mut digits).unwrap();
+        assert_eq!(
+            vec![2, 4],
+            digits.lines.iter().map(|(n, _)| *n).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn streams_context_and_stops_early() {
+        // 문맥을 포함해 받은 순서대로 기록하고, 첫 번째 일치에서 멈춤
+        struct First(Vec<String>);
+
+        impl Sink for First {
+            fn matched(&mut self, m: &Match<'_>) -> io::Result<bool> {
+                self.0.push(format!("{}:", m.line_number));
+                Ok(false)
+            }
+
+            fn context(&mut self, context: &Context<'_>) -> io::Result<bool> {
+                self.0.push(format!("{}-", context.line_number));
+                Ok(true)
+            }
+        }
+
+        let searcher = Searcher {
+            before_context: 1,
+            ..Searcher::new()
+        };
+        let matcher = PatternMatcher::regex(r"\d+").unwrap();
+        let mut first = First(Vec::new());
+        searcher.search_slice(&matcher, LOG, &mut first).unwrap();
+
+        assert_eq!(vec!["1-", "2:"], first.0);
+    }
+}
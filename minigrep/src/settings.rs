@@ -0,0 +1,155 @@
+//! 설정 파일과 저장된 검색 프로필
+//!
+//! `$XDG_CONFIG_HOME/minigrep/config.toml`(기본값은 `~/.config/minigrep/config.toml`)에서
+//! 옵션의 기본값을 읽음. 우선순위는 명령줄 옵션 > 설정 파일 > 환경변수
+//!
+//! ```toml
+//! color = "always"
+//! ignore-case = true
+//! exclude = ["*.min.js", "target/"]
+//!
+//! [profiles.errors]
+//! patterns = ["ERROR", "FATAL"]
+//! args = ["-n", "-w"]
+//! ```
+//!
+//! 위 설정에서 `minigrep @errors app.log`는 `minigrep -n -w -e ERROR -e FATAL app.log`와 같음
+use crate::color::ColorChoice;
+use crate::ConfigError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// 설정 파일 내용 (없는 항목은 명령줄이나 환경변수의 값을 사용)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Settings {
+    pub color: Option<ColorChoice>,
+    pub ignore_case: Option<bool>,
+    pub exclude: Vec<String>, // 디렉터리 탐색에서 제외할 glob (명령줄의 `--exclude`와 합쳐짐)
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// `@이름`으로 불러 쓰는 검색어와 옵션 묶음
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub patterns: Vec<String>, // 각각 `-e`로 전달
+    pub args: Vec<String>,     // 그 밖의 옵션
+}
+
+impl Settings {
+    /// 설정 파일을 읽고 (파일이 없으면 기본값), 설정 파일에 없는 항목은 환경변수로 채움
+    pub fn load() -> Result<Settings, ConfigError> {
+        let mut settings = Settings::read()?;
+        // 실행 명령어: IGNORE_CASE=1 cargo run -- to poem.txt
+        if settings.ignore_case.is_none() && env::var("IGNORE_CASE").is_ok() {
+            settings.ignore_case = Some(true);
+        }
+        Ok(settings)
+    }
+
+    fn read() -> Result<Settings, ConfigError> {
+        let Some(path) = path() else {
+            return Ok(Settings::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => Settings::parse(&text).map_err(|err| ConfigError::File(path, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(err) => Err(ConfigError::File(path, err.to_string())),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Settings, String> {
+        toml::from_str(text).map_err(|err| err.to_string())
+    }
+
+    /// 검색어 자리(`args[index]`)의 `@이름`을 프로필의 인수로 바꾼 인수 목록
+    /// 프로필 인수를 앞에 두어 명령줄에서 직접 준 옵션이 나중에 적용되도록 함
+    /// 해당하는 프로필이 없으면 `None`
+    pub fn expand(&self, args: &[String], index: usize) -> Option<Vec<String>> {
+        let profile = self.profiles.get(args.get(index)?.strip_prefix('@')?)?;
+
+        let mut expanded = vec![args[0].clone()];
+        expanded.extend(profile.args.iter().cloned());
+        for pattern in &profile.patterns {
+            expanded.push("-e".to_string());
+            expanded.push(pattern.clone());
+        }
+        expanded.extend(args[1..index].iter().cloned());
+        expanded.extend(args[index + 1..].iter().cloned());
+
+        Some(expanded)
+    }
+}
+
+// 설정 파일 경로
+// `MINIGREP_CONFIG`로 다른 파일을 지정할 수 있으며, 빈 값이면 설정 파일을 사용하지 않음
+fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("MINIGREP_CONFIG") {
+        return (!path.is_empty()).then(|| PathBuf::from(path));
+    }
+
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("minigrep").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_file() {
+        let settings = Settings::parse(
+            r#"
+            color = "never"
+            ignore-case = true
+            exclude = ["*.log"]
+
+            [profiles.errors]
+            patterns = ["ERROR", "FATAL"]
+            args = ["-n"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(Some(ColorChoice::Never), settings.color);
+        assert_eq!(Some(true), settings.ignore_case);
+        assert_eq!(vec!["*.log"], settings.exclude);
+        assert_eq!(vec!["ERROR", "FATAL"], settings.profiles["errors"].patterns);
+
+        assert!(Settings::parse("colour = \"never\"").is_err());
+    }
+
+    #[test]
+    fn expands_profile() {
+        let settings = Settings::parse(
+            r#"
+            [profiles.errors]
+            patterns = ["ERROR"]
+            args = ["-n"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(args(&["minigrep", "-n", "-e", "ERROR", "-c", "app.log"])),
+            settings.expand(&args(&["minigrep", "-c", "@errors", "app.log"]), 2)
+        );
+        assert_eq!(
+            None,
+            settings.expand(&args(&["minigrep", "@warnings", "app.log"]), 1)
+        );
+    }
+}
@@ -0,0 +1,24 @@
+//! 여러 모듈의 테스트에서 함께 쓰는 도우미
+//!
+//! 사용자의 설정 파일이나 `IGNORE_CASE` 환경변수에 따라 결과가 달라지지 않도록
+//! 항상 기본 설정(`Settings::default`)으로 `Config`를 만듦
+use crate::{Config, ConfigError, PatternMatcher, Settings};
+
+/// 프로그램 이름 뒤의 인수 `args`로 만든 설정
+pub(crate) fn try_config(args: &[&str]) -> Result<Config, ConfigError> {
+    let args: Vec<String> = ["minigrep"]
+        .iter()
+        .chain(args)
+        .map(|s| s.to_string())
+        .collect();
+    Config::build_with(&args, &Settings::default())
+}
+
+pub(crate) fn config(args: &[&str]) -> Config {
+    try_config(args).unwrap()
+}
+
+/// 검색어와 옵션 `args`로 만든 검색기 (경로가 없으면 표준 입력을 검색하는 설정)
+pub(crate) fn matcher(args: &[&str]) -> PatternMatcher {
+    PatternMatcher::new(&config(args)).unwrap()
+}
@@ -0,0 +1,175 @@
+//! 검색 대상 파일 탐색
+//!
+//! 명령줄로 받은 경로 목록을 실제로 검색할 파일 목록으로 펼침
+use crate::index;
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use std::iter;
+use std::path::{Path, PathBuf};
+
+// 바이너리 여부를 판단할 때 확인하는 앞부분의 크기 (grep과 같은 방식)
+const BINARY_CHECK_LEN: usize = 8 * 1024;
+
+/// 검색할 입력 하나
+pub enum Input {
+    Stdin, // `-` 또는 경로를 지정하지 않은 경우
+    File(PathBuf),
+}
+
+impl Input {
+    /// 출력에 사용할 경로 (표준 입력은 grep과 같은 이름 사용)
+    pub fn path(&self) -> &Path {
+        match self {
+            Input::Stdin => Path::new("(standard input)"),
+            Input::File(path) => path,
+        }
+    }
+}
+
+/// 경로 목록을 지정한 순서대로 순회하며 검색할 입력을 차례로 반환
+///
+/// 디렉터리는 재귀적으로 탐색하며, `.gitignore`/`.ignore` 규칙이나 `exclude` glob에 걸리는 파일은 건너뜀
+/// 명령줄에서 직접 지정한 파일은 무시 규칙과 상관없이 항상 포함됨
+pub fn inputs<'a>(
+    paths: &'a [String],
+    exclude: &'a [String],
+) -> impl Iterator<Item = Result<Input, ignore::Error>> + 'a {
+    paths.iter().flat_map(
+        move |path| -> Box<dyn Iterator<Item = Result<Input, ignore::Error>>> {
+            if path == "-" {
+                Box::new(iter::once(Ok(Input::Stdin)))
+            } else {
+                Box::new(files(path, exclude).map(|path| path.map(Input::File)))
+            }
+        },
+    )
+}
+
+pub(crate) fn files(
+    root: &str,
+    exclude: &[String],
+) -> Box<dyn Iterator<Item = Result<PathBuf, ignore::Error>>> {
+    // glob은 탐색을 시작한 디렉터리 기준으로 해석됨
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in exclude {
+        if let Err(err) = overrides.add(&format!("!{glob}")) {
+            return Box::new(iter::once(Err(err)));
+        }
+    }
+    let overrides = match overrides.build() {
+        Ok(overrides) => overrides,
+        Err(err) => return Box::new(iter::once(Err(err))),
+    };
+
+    let walk = WalkBuilder::new(root)
+        // git 저장소가 아니더라도 `.gitignore` 규칙을 적용
+        .require_git(false)
+        // 숨김 파일도 검색하되, `.git` 디렉터리 내부와 `--index`의 색인 파일은 제외
+        .hidden(false)
+        .filter_entry(|entry| entry.file_name() != ".git" && entry.file_name() != index::FILE_NAME)
+        .overrides(overrides)
+        // 디렉터리 안의 항목을 이름순으로 방문해 실행할 때마다 같은 순서로 출력
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    Box::new(walk.filter_map(|entry| match entry {
+        Ok(entry) if entry.path().is_file() => Some(Ok(entry.into_path())),
+        Ok(_) => None,
+        Err(err) => Some(Err(err)),
+    }))
+}
+
+/// 경로 목록 중에 디렉터리가 있는지 확인
+pub fn has_directory(paths: &[String]) -> bool {
+    paths.iter().any(|path| Path::new(path).is_dir())
+}
+
+/// 앞부분에 NUL 바이트가 있으면 바이너리 파일로 간주
+pub fn is_binary(bytes: &[u8]) -> bool {
+    let len = bytes.len().min(BINARY_CHECK_LEN);
+    bytes[..len].contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, process};
+
+    #[test]
+    fn skips_ignored_files() {
+        let root = env::temp_dir().join(format!("minigrep-walk-{}", process::id()));
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join(".ignore"), "skip/\n").unwrap();
+        fs::create_dir_all(root.join("skip")).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("sub/b.txt"), "b").unwrap();
+        fs::write(root.join("sub/c.log"), "c").unwrap();
+        fs::write(root.join("skip/d.txt"), "d").unwrap();
+
+        let found: Vec<PathBuf> = files(&root.display().to_string(), &[])
+            .map(Result::unwrap)
+            .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            vec![
+                PathBuf::from(".gitignore"),
+                PathBuf::from(".ignore"),
+                PathBuf::from("a.txt"),
+                PathBuf::from("sub/b.txt"),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn excludes_globs() {
+        let root = env::temp_dir().join(format!("minigrep-exclude-{}", process::id()));
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.min.js"), "b").unwrap();
+        fs::write(root.join("target/c.txt"), "c").unwrap();
+
+        let exclude = ["*.min.js".to_string(), "target/".to_string()];
+        let found: Vec<PathBuf> = files(&root.display().to_string(), &exclude)
+            .map(Result::unwrap)
+            .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        let invalid = files(&root.display().to_string(), &["a{".to_string()]).next();
+
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(vec![PathBuf::from("a.txt")], found);
+        assert!(invalid.unwrap().is_err());
+    }
+
+    #[test]
+    fn keeps_argument_order() {
+        let paths = [
+            "poem.txt".to_string(),
+            "-".to_string(),
+            "Cargo.toml".to_string(),
+        ];
+        let found: Vec<PathBuf> = inputs(&paths, &[])
+            .map(|input| input.unwrap().path().to_path_buf())
+            .collect();
+
+        assert_eq!(
+            vec![
+                PathBuf::from("poem.txt"),
+                PathBuf::from("(standard input)"),
+                PathBuf::from("Cargo.toml"),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn detects_binary() {
+        assert!(is_binary(b"\x7fELF\x00\x01"));
+        assert!(!is_binary("러스트 text".as_bytes()));
+    }
+}